@@ -5,7 +5,7 @@ use ratatui::{
     widgets::{ScrollbarState, TableState},
 };
 
-use crate::{helpers::app, maps, programs, uis};
+use crate::{helpers::app, loader, maps, programs, uis};
 
 const FOOTER_TEXT: [&str; 1] =
     ["(Esc) quit | (↑) move up | (↓) move down | (←) move left | (→) move right"];
@@ -15,6 +15,7 @@ const HEADER_TEXT: [&str; 1] = ["eMAN :: You UI friendly eBPF Manager"];
 pub enum MenuItem {
     Maps,
     Programs,
+    Load,
 }
 
 pub struct MainMenu {
@@ -28,9 +29,9 @@ impl MainMenu {
     pub fn new() -> Self {
         MainMenu {
             state: MenuItem::Maps,
-            items: vec![MenuItem::Maps, MenuItem::Programs],
+            items: vec![MenuItem::Maps, MenuItem::Programs, MenuItem::Load],
             table_state: TableState::default().with_selected(0),
-            scroll_state: ScrollbarState::new(2),
+            scroll_state: ScrollbarState::new(3),
         }
     }
 
@@ -74,12 +75,17 @@ impl MainMenu {
                             KeyCode::Enter => match self.state {
                                 MenuItem::Maps => {
                                     let maps = maps::Maps::new();
-                                    maps.run(terminal)?;
+                                    maps.run(&mut terminal)?;
                                     return Ok(());
                                 }
                                 MenuItem::Programs => {
                                     let programs = programs::Programs::new();
-                                    programs.run(terminal)?;
+                                    programs.run(&mut terminal)?;
+                                    return Ok(());
+                                }
+                                MenuItem::Load => {
+                                    let loader = loader::Loader::new();
+                                    loader.run(&mut terminal)?;
                                     return Ok(());
                                 }
                             },
@@ -109,6 +115,10 @@ impl MainMenu {
                 name: "2. Programs".to_string(),
                 description: "Manage eBPF programs".to_string(),
             },
+            app::MenuItem {
+                name: "3. Load".to_string(),
+                description: "Load eBPF programs from an ELF object file".to_string(),
+            },
         ];
         uis::table::render_table(
             frame,
@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+
+use crate::helpers::{
+    self,
+    btf::Btf,
+    dump::{self, DumpFormat},
+    graph::GraphFormat,
+    loader::BpfObject,
+};
+
+const USAGE: &str = "\
+usage:
+  eman                                          start the TUI
+  eman map export <MAP_ID> <json|csv|bin> <PATH>  dump a map to a file
+  eman map import <MAP_ID> <PATH> [--dry-run]     sync a map with a dump file
+  eman prog tail-calls [--dot]                    print the tail-call graph
+  eman graph <dot|mermaid> <PATH> [--prog <ID>]   export the program/map graph
+  eman load <OBJECT> [--prog <NAME>]... [--pin <DIR>]
+                                                load programs from an ELF object and
+                                                pin them in DIR/progs, their maps in
+                                                DIR/maps; without --pin they unload
+                                                on exit, which only checks they verify
+";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["map", "export", id, format, path] => map_export(id, format, path),
+        ["map", "import", id, path] => map_import(id, path, false),
+        ["map", "import", id, path, "--dry-run"] => map_import(id, path, true),
+        ["prog", "tail-calls"] => prog_tail_calls(false),
+        ["prog", "tail-calls", "--dot"] => prog_tail_calls(true),
+        ["graph", format, path] => graph_export(format, path, None),
+        ["graph", format, path, "--prog", id] => graph_export(format, path, Some(id)),
+        ["load", path, options @ ..] => load_object(path, options),
+        ["help"] | ["-h"] | ["--help"] => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => bail!("unknown command: {}\n{USAGE}", args.join(" ")),
+    }
+}
+
+fn parse_map_id(id: &str) -> Result<libbpf_sys::bpf_map_info> {
+    let id = id
+        .parse::<u32>()
+        .map_err(|_| eyre!("invalid map id {:?}", id))?;
+    let info = helpers::maps::fetch_maps_by_id(id);
+    if info.id != id {
+        bail!("map #{} not found", id);
+    }
+    Ok(info)
+}
+
+fn map_export(id: &str, format: &str, path: &str) -> Result<()> {
+    let info = parse_map_id(id)?;
+    let format =
+        DumpFormat::from_name(format).ok_or_else(|| eyre!("unknown format {:?}", format))?;
+    let n = dump::export_map(&info, Path::new(path), format)?;
+    println!("exported {} entries from map #{} to {}", n, info.id, path);
+    Ok(())
+}
+
+fn map_import(id: &str, path: &str, dry_run: bool) -> Result<()> {
+    let info = parse_map_id(id)?;
+    let dump = dump::read_dump(Path::new(path))?;
+    let plan = dump::plan_import(&info, &dump)?;
+    let btf = Btf::load_by_id(info.btf_id);
+    for line in dump::describe_plan(&info, &plan, btf.as_ref()) {
+        println!("{}", line);
+    }
+    if dry_run || plan.is_empty() {
+        return Ok(());
+    }
+
+    let (applied, failures) = dump::apply_import(&info, &plan);
+    for failure in &failures {
+        eprintln!("{}", failure);
+    }
+    println!("applied {} changes to map #{}", applied, info.id);
+    if !failures.is_empty() {
+        bail!("{} changes failed", failures.len());
+    }
+    Ok(())
+}
+
+fn prog_tail_calls(dot: bool) -> Result<()> {
+    let graph = helpers::graph::fetch_tail_call_graph();
+    if dot {
+        print!("{}", graph.to_dot());
+    } else {
+        for line in graph.tree() {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn graph_export(format: &str, path: &str, prog_id: Option<&str>) -> Result<()> {
+    let format =
+        GraphFormat::from_name(format).ok_or_else(|| eyre!("unknown format {:?}", format))?;
+    let mut graph = helpers::graph::fetch_usage_graph();
+    if let Some(id) = prog_id {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| eyre!("invalid program id {:?}", id))?;
+        if helpers::programs::fetch_programs_by_id(id).id != id {
+            bail!("program #{} not found", id);
+        }
+        graph = graph.neighborhood(id);
+    }
+    std::fs::write(path, graph.render(format))?;
+    println!("wrote {}", path);
+    Ok(())
+}
+
+fn load_object(path: &str, options: &[&str]) -> Result<()> {
+    let mut only = Vec::new();
+    let mut pin_dir = None;
+    let mut options = options.iter();
+    while let Some(&option) = options.next() {
+        match (option, options.next()) {
+            ("--prog", Some(&name)) => only.push(name),
+            ("--pin", Some(&dir)) => pin_dir = Some(dir.trim_end_matches('/')),
+            _ => bail!("unknown load option {:?}\n{USAGE}", option),
+        }
+    }
+
+    let mut object =
+        BpfObject::open(Path::new(path)).map_err(|e| eyre!("opening {} failed: {}", path, e))?;
+    if let Some(name) = only
+        .iter()
+        .find(|&&name| !object.programs.iter().any(|prog| prog.name == name))
+    {
+        bail!("{} has no program {:?}", path, name);
+    }
+    for prog in &mut object.programs {
+        prog.load = only.is_empty() || only.contains(&prog.name.as_str());
+        prog.pin = pin_dir.map(|dir| format!("{}/progs/{}", dir, prog.name));
+    }
+    // Programs and maps get separate directories, as they may share a name. Global data
+    // sections are internal to the object; only declared maps get pinned.
+    for map in object.maps.iter_mut().filter(|map| !map.internal) {
+        map.pin = pin_dir.map(|dir| format!("{}/maps/{}", dir, map.name));
+    }
+
+    let report = object.load();
+    for line in &report.lines {
+        println!("{}", line);
+    }
+    if !report.ok {
+        bail!("loading {} failed", path);
+    }
+    Ok(())
+}
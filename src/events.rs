@@ -0,0 +1,448 @@
+use std::{collections::VecDeque, time::Instant};
+
+use chrono::{DateTime, Local};
+use libbpf_sys::{BPF_MAP_TYPE_PERF_EVENT_ARRAY, bpf_map_info};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Constraint, Layout, Rect},
+    widgets::{Block, BorderType, Clear, Paragraph, TableState},
+};
+
+use crate::{
+    helpers::{
+        self,
+        btf::Btf,
+        maps::MapEntryRow,
+        perfbuf::{PerfBuffer, PerfRecord},
+        ringbuf::RingBuffer,
+    },
+    uis::{self, input::TextInput},
+    utils::{self, btf::BtfValue},
+};
+
+// Oldest events are evicted from the log past this many.
+const LOG_CAPACITY: usize = 1000;
+// Bytes of a record shown in the log line when it is not BTF-decoded.
+const HEX_PREVIEW: usize = 32;
+// Backlog above this share of the ring means producers are about to fail reservations.
+const FULL_PERCENT: u64 = 90;
+
+enum Source {
+    Ring(RingBuffer),
+    Perf(PerfBuffer),
+}
+
+enum Payload {
+    Sample(Vec<u8>),
+    Lost(u64),
+}
+
+struct Event {
+    at: DateTime<Local>,
+    cpu: Option<u32>,
+    payload: Payload,
+    summary: String,
+}
+
+/// Live view of a ring buffer or perf event array map: consumes records as they arrive
+/// and keeps a rolling log.
+pub struct EventView {
+    item: bpf_map_info,
+    source: Option<Source>,
+    // Shown before attaching to the map; nothing is touched until the user accepts it.
+    confirm: Option<Vec<String>>,
+    error: Option<String>,
+    events: VecDeque<Event>,
+    total: u64,
+    lost: u64,
+    evicted: u64,
+    full_seen: u64,
+    full: bool,
+    paused: bool,
+    rate: f64,
+    rate_count: u64,
+    rate_since: Instant,
+    btf: Option<Btf>,
+    type_id: u32,
+    hex: bool,
+    type_input: Option<TextInput>,
+    type_error: Option<String>,
+    state: TableState,
+}
+
+impl EventView {
+    pub fn new(item: bpf_map_info) -> Self {
+        Self {
+            item,
+            source: None,
+            confirm: Some(attach_warning(&item)),
+            error: None,
+            events: VecDeque::new(),
+            total: 0,
+            lost: 0,
+            evicted: 0,
+            full_seen: 0,
+            full: false,
+            paused: false,
+            rate: 0.0,
+            rate_count: 0,
+            rate_since: Instant::now(),
+            btf: helpers::maps::fetch_map_btf(&item),
+            type_id: 0,
+            hex: false,
+            type_input: None,
+            type_error: None,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    fn attach(&mut self) {
+        let source = match self.item.type_ {
+            BPF_MAP_TYPE_PERF_EVENT_ARRAY => PerfBuffer::open(&self.item).map(Source::Perf),
+            _ => RingBuffer::open(&self.item).map(Source::Ring),
+        };
+        match source {
+            Ok(source) => self.source = Some(source),
+            Err(e) => self.error = Some(format!("cannot open {}: {e}", kind_name(&self.item))),
+        }
+    }
+
+    /// Drains whatever producers committed since the last call, unless paused.
+    pub fn poll(&mut self) {
+        match self.source.as_mut().filter(|_| !self.paused) {
+            Some(Source::Ring(ring)) => {
+                let mut records = Vec::new();
+                ring.consume(|data| records.push(data.to_vec()));
+                for data in records {
+                    self.push(None, Payload::Sample(data));
+                }
+            }
+            Some(Source::Perf(perf)) => {
+                for record in perf.consume() {
+                    match record {
+                        PerfRecord::Sample { cpu, data } => {
+                            self.push(Some(cpu), Payload::Sample(data))
+                        }
+                        PerfRecord::Lost { cpu, count } => {
+                            self.lost += count;
+                            self.push(Some(cpu), Payload::Lost(count));
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+        if let Some(Source::Ring(ring)) = &self.source {
+            let full = ring.pending() * 100 >= ring.size() as u64 * FULL_PERCENT;
+            if full && !self.full {
+                self.full_seen += 1;
+            }
+            self.full = full;
+        }
+
+        let elapsed = self.rate_since.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            self.rate = self.rate_count as f64 / elapsed;
+            self.rate_count = 0;
+            self.rate_since = Instant::now();
+        }
+    }
+
+    fn push(&mut self, cpu: Option<u32>, payload: Payload) {
+        let summary = self.summarize(&payload);
+        self.events.push_front(Event {
+            at: Local::now(),
+            cpu,
+            payload,
+            summary,
+        });
+        if self.events.len() > LOG_CAPACITY {
+            self.events.pop_back();
+            self.evicted += 1;
+        }
+        self.total += 1;
+        self.rate_count += 1;
+        // Keep the selection on the same event while the log grows above it.
+        if let Some(i) = self.state.selected().filter(|&i| i > 0) {
+            self.state.select(Some((i + 1).min(self.events.len() - 1)));
+        }
+    }
+
+    fn decoder(&self) -> Option<&Btf> {
+        self.btf.as_ref().filter(|_| !self.hex && self.type_id != 0)
+    }
+
+    fn summarize(&self, payload: &Payload) -> String {
+        let data = match payload {
+            Payload::Sample(data) => data,
+            Payload::Lost(count) => return format!("lost {count} samples"),
+        };
+        match self.decoder() {
+            Some(btf) => utils::btf::format_inline(&btf.decode(self.type_id, data)),
+            None => {
+                let mut text = utils::btf::format_inline(&BtfValue::Bytes(
+                    data.iter().take(HEX_PREVIEW).copied().collect(),
+                ));
+                if data.len() > HEX_PREVIEW {
+                    text.push_str(" …");
+                }
+                text
+            }
+        }
+    }
+
+    fn resummarize(&mut self) {
+        let summaries = self
+            .events
+            .iter()
+            .map(|e| self.summarize(&e.payload))
+            .collect::<Vec<_>>();
+        for (event, summary) in self.events.iter_mut().zip(summaries) {
+            event.summary = summary;
+        }
+    }
+
+    fn set_type(&mut self, name: &str) -> Result<(), String> {
+        let btf = self
+            .btf
+            .as_ref()
+            .ok_or("no BTF found for this map or its programs")?;
+        let name = name.trim();
+        if name.is_empty() {
+            self.type_id = 0;
+        } else {
+            self.type_id = btf
+                .find_type(name)
+                .ok_or_else(|| format!("no struct, union or typedef named {name}"))?;
+        }
+        self.type_error = None;
+        self.resummarize();
+        Ok(())
+    }
+
+    /// Returns false when the user leaves the view.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.confirm.is_some() {
+            match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.confirm = None;
+                    self.attach();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => {
+                    return false;
+                }
+                _ => {}
+            }
+            return true;
+        }
+        if let Some(mut input) = self.type_input.take() {
+            match code {
+                KeyCode::Esc => self.type_error = None,
+                KeyCode::Enter => {
+                    if let Err(e) = self.set_type(&input.value) {
+                        self.type_error = Some(e);
+                        self.type_input = Some(input);
+                    }
+                }
+                _ => {
+                    input.handle_key(code);
+                    self.type_input = Some(input);
+                }
+            }
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('b') | KeyCode::Backspace => {
+                return false;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.next_row(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
+            KeyCode::Char('g') => self.state.select(Some(0)),
+            KeyCode::Char('p') | KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('c') => {
+                self.events.clear();
+                self.state.select(Some(0));
+            }
+            KeyCode::Char('x') => {
+                self.hex = !self.hex;
+                self.resummarize();
+            }
+            KeyCode::Char('t') => {
+                let current = match &self.btf {
+                    Some(btf) if self.type_id != 0 => btf.type_name(self.type_id),
+                    _ => String::new(),
+                };
+                self.type_input = Some(TextInput::new(current));
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn next_row(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state
+                .select(Some((i + 1).min(self.events.len().saturating_sub(1))));
+        }
+    }
+
+    fn previous_row(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    fn status(&self) -> String {
+        let mut status = format!("{:.1} ev/s | {} total", self.rate, self.total);
+        if let Some(Source::Ring(ring)) = &self.source {
+            let pending = ring.pending();
+            status += &format!(
+                " | backlog {}B ({}%)",
+                pending,
+                pending * 100 / ring.size().max(1) as u64
+            );
+            if self.full {
+                status += " | FULL: producers dropping";
+            } else if self.full_seen > 0 {
+                status += &format!(" | was full {}x", self.full_seen);
+            }
+        }
+        if self.lost > 0 {
+            status += &format!(" | {} lost", self.lost);
+        }
+        if self.evicted > 0 {
+            status += &format!(" | {} evicted from log", self.evicted);
+        }
+        if self.paused {
+            status += " | PAUSED";
+        }
+        if let Some(e) = &self.error {
+            status += &format!(" | {e}");
+        }
+        status
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, rects: &[Rect]) {
+        let map = self.item;
+        let decoding = match &self.btf {
+            Some(btf) if self.decoder().is_some() => btf.type_name(self.type_id),
+            Some(_) => "hex (BTF available)".to_string(),
+            None => "hex".to_string(),
+        };
+        let title = format!(
+            "{} #{} :: {} :: {} :: {}",
+            kind_name(&map),
+            map.id,
+            utils::programs::cstring_from_i8_array(map.name).unwrap_or_else(|| "N/A".into()),
+            match map.type_ {
+                BPF_MAP_TYPE_PERF_EVENT_ARRAY => format!("{} CPU slots", map.max_entries),
+                _ => format!("{} KiB", map.max_entries / 1024),
+            },
+            decoding
+        );
+        uis::header::render_header(&[&title], frame, rects[0]);
+
+        let [status, log, detail] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(12),
+        ])
+        .areas(rects[1]);
+        frame.render_widget(Paragraph::new(self.status()).centered(), status);
+        let per_cpu = map.type_ == BPF_MAP_TYPE_PERF_EVENT_ARRAY;
+        let rows = self
+            .events
+            .iter()
+            .map(|e| {
+                let mut columns = vec![vec![e.at.format("%H:%M:%S%.3f").to_string()]];
+                if per_cpu {
+                    columns.push(vec![e.cpu.map_or_else(String::new, |c| c.to_string())]);
+                }
+                columns.push(vec![match &e.payload {
+                    Payload::Sample(data) => format!("{}B", data.len()),
+                    Payload::Lost(_) => "-".to_string(),
+                }]);
+                columns.push(vec![e.summary.clone()]);
+                MapEntryRow { columns }
+            })
+            .collect::<Vec<_>>();
+        let headers: &[&str] = if per_cpu {
+            &["Time", "CPU", "Size", "Record"]
+        } else {
+            &["Time", "Size", "Record"]
+        };
+        uis::table::render_table(frame, log, &rows, &mut self.state, headers);
+
+        let lines = match self.state.selected().and_then(|i| self.events.get(i)) {
+            Some(Event {
+                payload: Payload::Sample(data),
+                ..
+            }) => helpers::maps::format_map_bytes(self.decoder(), self.type_id, data),
+            Some(event) => vec![event.summary.clone()],
+            None => vec!["waiting for events…".to_string()],
+        };
+        frame.render_widget(
+            Paragraph::new(lines.join("\n")).block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title("Record"),
+            ),
+            detail,
+        );
+
+        uis::footer::render_footer(
+            &[
+                "(Esc/q/b) back | (↑/↓) move | (g) newest | (p) pause | (c) clear | (t) record type | (x) hex/BTF",
+            ],
+            frame,
+            rects[2],
+        );
+
+        if let Some(lines) = &self.confirm {
+            uis::popup::render_popup(frame, "Confirm", lines);
+        }
+        if let Some(input) = &self.type_input {
+            let area = uis::popup::popup_area(frame.area(), 60, 3);
+            frame.render_widget(Clear, area);
+            let title = match &self.type_error {
+                Some(e) => format!("Record type :: {e}"),
+                None => "Record type (struct, union or typedef; empty for hex)".to_string(),
+            };
+            uis::input::render_input(frame, area, &title, input, true);
+        }
+    }
+}
+
+/// What attaching to `item` does to the map's owner, which the user has to accept first.
+fn attach_warning(item: &bpf_map_info) -> Vec<String> {
+    let mut lines = match item.type_ {
+        BPF_MAP_TYPE_PERF_EVENT_ARRAY => vec![
+            format!("Attach to perf event array #{}?", item.id),
+            "This replaces the perf event in every CPU slot of the map, and closing the view"
+                .to_string(),
+            "deletes them. The change is permanent: the program that owns the map stops"
+                .to_string(),
+            "receiving samples and does not get them back after eman closes.".to_string(),
+        ],
+        _ => vec![
+            format!("Consume ring buffer #{}?", item.id),
+            "Records are consumed, not copied: every record shown here is taken away from"
+                .to_string(),
+            "the map's own consumer, and both race for the same consumer position while"
+                .to_string(),
+            "the view is open.".to_string(),
+        ],
+    };
+    lines.push(String::new());
+    lines.push("(y) attach | (n/Esc) back".to_string());
+    lines
+}
+
+fn kind_name(item: &bpf_map_info) -> &'static str {
+    match item.type_ {
+        BPF_MAP_TYPE_PERF_EVENT_ARRAY => "Perf event array",
+        _ => "Ring buffer",
+    }
+}
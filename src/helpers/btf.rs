@@ -0,0 +1,608 @@
+use std::ffi::{CStr, CString};
+
+use libbpf_sys::{
+    BTF_INT_BOOL, BTF_INT_CHAR, BTF_INT_SIGNED, BTF_KIND_ARRAY, BTF_KIND_CONST, BTF_KIND_DATASEC,
+    BTF_KIND_ENUM, BTF_KIND_ENUM64, BTF_KIND_FLOAT, BTF_KIND_FUNC, BTF_KIND_FUNC_PROTO,
+    BTF_KIND_FWD, BTF_KIND_INT, BTF_KIND_PTR, BTF_KIND_RESTRICT, BTF_KIND_STRUCT,
+    BTF_KIND_TYPE_TAG, BTF_KIND_TYPEDEF, BTF_KIND_UNION, BTF_KIND_VAR, BTF_KIND_VOLATILE, btf,
+    btf__find_by_name_kind, btf__free, btf__load_from_kernel_by_id, btf__load_vmlinux_btf,
+    btf__name_by_offset, btf__resolve_size, btf__type_by_id, btf_array, btf_enum, btf_enum64,
+    btf_member, btf_param, btf_type, btf_var_secinfo,
+};
+
+use crate::utils::{
+    btf::{BtfValue, Literal},
+    maps::int_bits,
+};
+
+// Guards against self-referencing or absurdly nested types.
+const MAX_DEPTH: usize = 16;
+
+pub struct Btf {
+    ptr: *mut btf,
+}
+
+impl Drop for Btf {
+    fn drop(&mut self) {
+        unsafe { btf__free(self.ptr) };
+    }
+}
+
+impl Btf {
+    pub fn load_by_id(id: u32) -> Option<Self> {
+        if id == 0 {
+            return None;
+        }
+        let ptr = unsafe { btf__load_from_kernel_by_id(id) };
+        if ptr.is_null() {
+            return None;
+        }
+        Some(Self { ptr })
+    }
+
+    /// The running kernel's own BTF, which kfuncs are described in.
+    pub fn load_vmlinux() -> Option<Self> {
+        let ptr = unsafe { btf__load_vmlinux_btf() };
+        if ptr.is_null() {
+            return None;
+        }
+        Some(Self { ptr })
+    }
+
+    pub fn func_name(&self, type_id: u32) -> Option<String> {
+        let t = self.type_by_id(type_id)?;
+        (btf_kind(t) == BTF_KIND_FUNC).then(|| self.name(t.name_off))
+    }
+
+    /// Renders a BTF_KIND_FUNC as its C prototype, e.g. `int handle(struct xdp_md *ctx)`.
+    pub fn func_prototype(&self, type_id: u32) -> Option<String> {
+        let func = self
+            .type_by_id(type_id)
+            .filter(|t| btf_kind(t) == BTF_KIND_FUNC)?;
+        let proto = self
+            .type_by_id(unsafe { func.__bindgen_anon_1.type_ })
+            .filter(|t| btf_kind(t) == BTF_KIND_FUNC_PROTO)?;
+        let params =
+            unsafe { std::slice::from_raw_parts(trailing::<btf_param>(proto), btf_vlen(proto)) };
+        let params = params
+            .iter()
+            .map(|p| match p.type_ {
+                0 => "...".to_string(),
+                type_id => self.c_decl(type_id, &self.name(p.name_off), 0),
+            })
+            .collect::<Vec<_>>();
+        let name = self.name(func.name_off);
+        Some(self.c_decl(
+            unsafe { proto.__bindgen_anon_1.type_ },
+            &format!(
+                "{}({})",
+                name,
+                if params.is_empty() {
+                    "void".to_string()
+                } else {
+                    params.join(", ")
+                }
+            ),
+            0,
+        ))
+    }
+
+    pub fn decode(&self, type_id: u32, data: &[u8]) -> BtfValue {
+        self.decode_type(type_id, data, 0)
+    }
+
+    pub fn type_name(&self, type_id: u32) -> String {
+        self.type_by_id(type_id)
+            .map(|t| self.name(t.name_off))
+            .unwrap_or_default()
+    }
+
+    /// Looks up a named struct, union or typedef, e.g. the layout of ring buffer records.
+    pub fn find_type(&self, name: &str) -> Option<u32> {
+        let name = CString::new(name).ok()?;
+        [BTF_KIND_STRUCT, BTF_KIND_UNION, BTF_KIND_TYPEDEF]
+            .into_iter()
+            .find_map(|kind| {
+                let id = unsafe { btf__find_by_name_kind(self.ptr, name.as_ptr(), kind) };
+                u32::try_from(id).ok().filter(|&id| id != 0)
+            })
+    }
+
+    fn type_by_id(&self, type_id: u32) -> Option<&btf_type> {
+        unsafe { btf__type_by_id(self.ptr, type_id).as_ref() }
+    }
+
+    /// Reads an entry of the string section, e.g. a line_info file name or source line.
+    pub fn name(&self, offset: u32) -> String {
+        let name = unsafe { btf__name_by_offset(self.ptr, offset) };
+        if name.is_null() {
+            return String::new();
+        }
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn resolve_size(&self, type_id: u32) -> Option<usize> {
+        let size = unsafe { btf__resolve_size(self.ptr, type_id) };
+        usize::try_from(size).ok()
+    }
+
+    fn decode_type(&self, type_id: u32, data: &[u8], depth: usize) -> BtfValue {
+        let Some(t) = self.type_by_id(type_id) else {
+            return BtfValue::Bytes(data.to_vec());
+        };
+        if depth > MAX_DEPTH {
+            return BtfValue::Bytes(data.to_vec());
+        }
+
+        match btf_kind(t) {
+            BTF_KIND_INT => {
+                let int_info = unsafe { *trailing::<u32>(t) };
+                let encoding = (int_info >> 24) & 0x0f;
+                let offset = ((int_info >> 16) & 0xff) as usize;
+                let bits = (int_info & 0xff) as usize;
+                self.int_value(read_bits(data, offset, bits), bits, encoding)
+            }
+            BTF_KIND_PTR => BtfValue::Pointer(read_bits(data, 0, data.len().min(8) * 8) as u64),
+            BTF_KIND_ARRAY => {
+                let array = unsafe { *trailing::<btf_array>(t) };
+                let Some(elem_size) = self.resolve_size(array.type_) else {
+                    return BtfValue::Bytes(data.to_vec());
+                };
+                if elem_size == 1 && self.is_char(array.type_) {
+                    let end = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+                    return BtfValue::Str(String::from_utf8_lossy(&data[..end]).into_owned());
+                }
+                let elems = (0..array.nelems as usize)
+                    .map_while(|i| data.get(i * elem_size..(i + 1) * elem_size))
+                    .map(|chunk| self.decode_type(array.type_, chunk, depth + 1))
+                    .collect();
+                BtfValue::Array(elems)
+            }
+            BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                let kind = if btf_kind(t) == BTF_KIND_STRUCT {
+                    "struct"
+                } else {
+                    "union"
+                };
+                let members =
+                    unsafe { std::slice::from_raw_parts(trailing::<btf_member>(t), btf_vlen(t)) };
+                let fields = members
+                    .iter()
+                    .map(|m| {
+                        let (bit_offset, bitfield_size) = if btf_kflag(t) {
+                            ((m.offset & 0xffffff) as usize, (m.offset >> 24) as usize)
+                        } else {
+                            (m.offset as usize, 0)
+                        };
+                        let value = if bitfield_size > 0 {
+                            self.bitfield_value(m.type_, data, bit_offset, bitfield_size)
+                        } else {
+                            let start = (bit_offset / 8).min(data.len());
+                            let end = self
+                                .resolve_size(m.type_)
+                                .map_or(data.len(), |size| (start + size).min(data.len()));
+                            self.decode_type(m.type_, &data[start..end], depth + 1)
+                        };
+                        (self.name(m.name_off), value)
+                    })
+                    .collect();
+                BtfValue::Record {
+                    kind,
+                    name: self.name(t.name_off),
+                    fields,
+                }
+            }
+            BTF_KIND_ENUM | BTF_KIND_ENUM64 => {
+                let size = unsafe { t.__bindgen_anon_1.size } as usize;
+                let bits = size.min(8) * 8;
+                self.enum_value(t, read_bits(data, 0, bits), bits)
+            }
+            BTF_KIND_FLOAT => match data.len() {
+                4 => BtfValue::Float(f32::from_le_bytes(data.try_into().unwrap()) as f64),
+                8 => BtfValue::Float(f64::from_le_bytes(data.try_into().unwrap())),
+                _ => BtfValue::Bytes(data.to_vec()),
+            },
+            BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+            | BTF_KIND_TYPE_TAG | BTF_KIND_VAR => {
+                self.decode_type(unsafe { t.__bindgen_anon_1.type_ }, data, depth)
+            }
+            BTF_KIND_DATASEC => {
+                let vars = unsafe {
+                    std::slice::from_raw_parts(trailing::<btf_var_secinfo>(t), btf_vlen(t))
+                };
+                let fields = vars
+                    .iter()
+                    .filter_map(|v| {
+                        let chunk = data.get(v.offset as usize..(v.offset + v.size) as usize)?;
+                        Some((
+                            self.type_name(v.type_),
+                            self.decode_type(v.type_, chunk, depth + 1),
+                        ))
+                    })
+                    .collect();
+                BtfValue::Record {
+                    kind: "section",
+                    name: self.name(t.name_off),
+                    fields,
+                }
+            }
+            _ => BtfValue::Bytes(data.to_vec()),
+        }
+    }
+
+    /// Encodes a user-typed literal into a zeroed buffer sized for `type_id`.
+    pub fn encode(&self, type_id: u32, literal: &Literal) -> Result<Vec<u8>, String> {
+        let size = self
+            .resolve_size(type_id)
+            .ok_or_else(|| format!("cannot size BTF type {}", type_id))?;
+        let mut buf = vec![0u8; size];
+        self.encode_type(type_id, literal, &mut buf, 0)?;
+        Ok(buf)
+    }
+
+    fn encode_type(
+        &self,
+        type_id: u32,
+        literal: &Literal,
+        buf: &mut [u8],
+        depth: usize,
+    ) -> Result<(), String> {
+        let t = self
+            .type_by_id(type_id)
+            .ok_or_else(|| format!("unknown BTF type {}", type_id))?;
+        if depth > MAX_DEPTH {
+            return Err("type nesting too deep".to_string());
+        }
+
+        match btf_kind(t) {
+            BTF_KIND_INT => {
+                let int_info = unsafe { *trailing::<u32>(t) };
+                let offset = ((int_info >> 16) & 0xff) as usize;
+                let bits = (int_info & 0xff) as usize;
+                let signed = (int_info >> 24) & BTF_INT_SIGNED != 0;
+                write_bits(
+                    buf,
+                    offset,
+                    bits,
+                    int_bits(self.integer(literal, None)?, bits, signed)?,
+                );
+                Ok(())
+            }
+            BTF_KIND_PTR => {
+                let bits = buf.len().min(8) * 8;
+                write_bits(
+                    buf,
+                    0,
+                    bits,
+                    int_bits(self.integer(literal, None)?, bits, false)?,
+                );
+                Ok(())
+            }
+            BTF_KIND_ENUM | BTF_KIND_ENUM64 => {
+                let bits = buf.len().min(8) * 8;
+                let value = self.integer(literal, Some(t))?;
+                write_bits(buf, 0, bits, int_bits(value, bits, btf_kflag(t))?);
+                Ok(())
+            }
+            BTF_KIND_FLOAT => {
+                let value = match literal {
+                    Literal::Float(v) => *v,
+                    Literal::Int(v) => *v as f64,
+                    _ => return Err("expected a number".to_string()),
+                };
+                match buf.len() {
+                    4 => buf.copy_from_slice(&(value as f32).to_le_bytes()),
+                    8 => buf.copy_from_slice(&value.to_le_bytes()),
+                    n => return Err(format!("unsupported {}-byte float", n)),
+                }
+                Ok(())
+            }
+            BTF_KIND_ARRAY => {
+                let array = unsafe { *trailing::<btf_array>(t) };
+                let elem_size = self
+                    .resolve_size(array.type_)
+                    .ok_or("cannot size array element")?;
+                match literal {
+                    Literal::Str(s) if elem_size == 1 => {
+                        if s.len() > buf.len() {
+                            return Err(format!("string longer than {} bytes", buf.len()));
+                        }
+                        buf[..s.len()].copy_from_slice(s.as_bytes());
+                        Ok(())
+                    }
+                    Literal::List(elems) => {
+                        if elems.len() > array.nelems as usize {
+                            return Err(format!("array holds at most {} elements", array.nelems));
+                        }
+                        for (i, elem) in elems.iter().enumerate() {
+                            let chunk = &mut buf[i * elem_size..(i + 1) * elem_size];
+                            self.encode_type(array.type_, elem, chunk, depth + 1)?;
+                        }
+                        Ok(())
+                    }
+                    _ => Err("expected a list or string for an array".to_string()),
+                }
+            }
+            BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                let Literal::Record(fields) = literal else {
+                    return Err(format!("expected {{...}} for {}", self.name(t.name_off)));
+                };
+                let members =
+                    unsafe { std::slice::from_raw_parts(trailing::<btf_member>(t), btf_vlen(t)) };
+                for (name, value) in fields {
+                    let m = members
+                        .iter()
+                        .find(|m| self.name(m.name_off) == *name)
+                        .ok_or_else(|| format!("no field {:?}", name))?;
+                    let (bit_offset, bitfield_size) = if btf_kflag(t) {
+                        ((m.offset & 0xffffff) as usize, (m.offset >> 24) as usize)
+                    } else {
+                        (m.offset as usize, 0)
+                    };
+                    if bitfield_size > 0 {
+                        let target = self.type_by_id(self.skip_modifiers(m.type_));
+                        let enum_type = target
+                            .filter(|t| matches!(btf_kind(t), BTF_KIND_ENUM | BTF_KIND_ENUM64));
+                        let signed = match target {
+                            Some(t) if btf_kind(t) == BTF_KIND_INT => {
+                                (unsafe { *trailing::<u32>(t) } >> 24) & BTF_INT_SIGNED != 0
+                            }
+                            _ => enum_type.is_some_and(btf_kflag),
+                        };
+                        let raw = int_bits(self.integer(value, enum_type)?, bitfield_size, signed)
+                            .map_err(|e| format!("field {}: {}", name, e))?;
+                        write_bits(buf, bit_offset, bitfield_size, raw);
+                    } else {
+                        let start = bit_offset / 8;
+                        let size = self
+                            .resolve_size(m.type_)
+                            .ok_or_else(|| format!("cannot size field {}", name))?;
+                        let chunk = buf
+                            .get_mut(start..start + size)
+                            .ok_or_else(|| format!("field {} out of bounds", name))?;
+                        self.encode_type(m.type_, value, chunk, depth + 1)?;
+                    }
+                }
+                Ok(())
+            }
+            BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+            | BTF_KIND_TYPE_TAG | BTF_KIND_VAR => {
+                self.encode_type(unsafe { t.__bindgen_anon_1.type_ }, literal, buf, depth)
+            }
+            _ => Err(format!("cannot enter values of BTF kind {}", btf_kind(t))),
+        }
+    }
+
+    fn integer(&self, literal: &Literal, enum_type: Option<&btf_type>) -> Result<i128, String> {
+        match literal {
+            Literal::Int(v) => Ok(*v),
+            Literal::Char(c) => Ok(*c as i128),
+            Literal::Ident(name) if name == "true" => Ok(1),
+            Literal::Ident(name) if name == "false" => Ok(0),
+            Literal::Ident(ident) => enum_type
+                .and_then(|t| {
+                    self.enumerators(t)
+                        .into_iter()
+                        .find(|(name, _)| name == ident)
+                })
+                .map(|(_, value)| value)
+                .ok_or_else(|| format!("unknown enumerator {:?}", ident)),
+            _ => Err("expected an integer".to_string()),
+        }
+    }
+
+    /// The enum's enumerators and their values, signed only if its kflag says so.
+    fn enumerators(&self, t: &btf_type) -> Vec<(String, i128)> {
+        let signed = btf_kflag(t);
+        if btf_kind(t) == BTF_KIND_ENUM {
+            let values =
+                unsafe { std::slice::from_raw_parts(trailing::<btf_enum>(t), btf_vlen(t)) };
+            values
+                .iter()
+                .map(|e| {
+                    let val = if signed {
+                        e.val as i128
+                    } else {
+                        e.val as u32 as i128
+                    };
+                    (self.name(e.name_off), val)
+                })
+                .collect()
+        } else {
+            let values =
+                unsafe { std::slice::from_raw_parts(trailing::<btf_enum64>(t), btf_vlen(t)) };
+            values
+                .iter()
+                .map(|e| {
+                    let val = ((e.val_hi32 as u64) << 32) | e.val_lo32 as u64;
+                    let val = if signed {
+                        val as i64 as i128
+                    } else {
+                        val as i128
+                    };
+                    (self.name(e.name_off), val)
+                })
+                .collect()
+        }
+    }
+
+    fn int_value(&self, raw: u128, bits: usize, encoding: u32) -> BtfValue {
+        if encoding & BTF_INT_BOOL != 0 {
+            BtfValue::Bool(raw != 0)
+        } else if encoding & BTF_INT_CHAR != 0 && bits == 8 {
+            BtfValue::Char(raw as u8)
+        } else if encoding & BTF_INT_SIGNED != 0 {
+            BtfValue::Signed(sign_extend(raw, bits))
+        } else {
+            BtfValue::Unsigned(raw)
+        }
+    }
+
+    fn bitfield_value(
+        &self,
+        type_id: u32,
+        data: &[u8],
+        bit_offset: usize,
+        bits: usize,
+    ) -> BtfValue {
+        let raw = read_bits(data, bit_offset, bits);
+        let Some(t) = self.type_by_id(self.skip_modifiers(type_id)) else {
+            return BtfValue::Unsigned(raw);
+        };
+        match btf_kind(t) {
+            BTF_KIND_INT => {
+                let encoding = (unsafe { *trailing::<u32>(t) } >> 24) & 0x0f;
+                self.int_value(raw, bits, encoding)
+            }
+            BTF_KIND_ENUM | BTF_KIND_ENUM64 => self.enum_value(t, raw, bits),
+            _ => BtfValue::Unsigned(raw),
+        }
+    }
+
+    /// Names the enumerator whose value is the low `bits` bits in `raw`, which are
+    /// sign-extended only for signed enums.
+    fn enum_value(&self, t: &btf_type, raw: u128, bits: usize) -> BtfValue {
+        let value = if btf_kflag(t) {
+            sign_extend(raw, bits)
+        } else {
+            raw as i128
+        };
+        let name = self
+            .enumerators(t)
+            .into_iter()
+            .find(|(_, v)| *v == value)
+            .map(|(name, _)| name);
+        BtfValue::Enum { name, value }
+    }
+
+    fn is_char(&self, type_id: u32) -> bool {
+        self.type_by_id(self.skip_modifiers(type_id))
+            .is_some_and(|t| {
+                btf_kind(t) == BTF_KIND_INT
+                    && (unsafe { *trailing::<u32>(t) } & 0xff) == 8
+                    && matches!(
+                        self.name(t.name_off).as_str(),
+                        "char" | "signed char" | "unsigned char"
+                    )
+            })
+    }
+
+    /// Spells out a declaration of `name` with the given type, e.g. `const char *fmt`.
+    fn c_decl(&self, type_id: u32, name: &str, depth: usize) -> String {
+        let spell = |base: String| match name {
+            "" => base,
+            _ => format!("{} {}", base, name),
+        };
+        if type_id == 0 {
+            return spell("void".to_string());
+        }
+        let Some(t) = self.type_by_id(type_id).filter(|_| depth < MAX_DEPTH) else {
+            return spell("?".to_string());
+        };
+        let inner = unsafe { t.__bindgen_anon_1.type_ };
+        match btf_kind(t) {
+            BTF_KIND_PTR => self.c_decl(inner, &format!("*{}", name), depth + 1),
+            BTF_KIND_CONST => format!("const {}", self.c_decl(inner, name, depth + 1)),
+            BTF_KIND_VOLATILE => format!("volatile {}", self.c_decl(inner, name, depth + 1)),
+            BTF_KIND_RESTRICT | BTF_KIND_TYPE_TAG => self.c_decl(inner, name, depth + 1),
+            BTF_KIND_ARRAY => {
+                let array = unsafe { &*trailing::<btf_array>(t) };
+                let name = if name.starts_with('*') {
+                    format!("({})[{}]", name, array.nelems)
+                } else {
+                    format!("{}[{}]", name, array.nelems)
+                };
+                self.c_decl(array.type_, &name, depth + 1)
+            }
+            BTF_KIND_FUNC_PROTO => {
+                let params =
+                    unsafe { std::slice::from_raw_parts(trailing::<btf_param>(t), btf_vlen(t)) };
+                let params = params
+                    .iter()
+                    .map(|p| match p.type_ {
+                        0 => "...".to_string(),
+                        type_id => self.c_decl(type_id, "", depth + 1),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.c_decl(inner, &format!("({})({})", name, params), depth + 1)
+            }
+            BTF_KIND_STRUCT => spell(format!("struct {}", self.name(t.name_off))),
+            BTF_KIND_UNION => spell(format!("union {}", self.name(t.name_off))),
+            BTF_KIND_ENUM | BTF_KIND_ENUM64 => spell(format!("enum {}", self.name(t.name_off))),
+            BTF_KIND_FWD if btf_kflag(t) => spell(format!("union {}", self.name(t.name_off))),
+            BTF_KIND_FWD => spell(format!("struct {}", self.name(t.name_off))),
+            _ => spell(self.name(t.name_off)),
+        }
+    }
+
+    fn skip_modifiers(&self, mut type_id: u32) -> u32 {
+        for _ in 0..MAX_DEPTH {
+            match self.type_by_id(type_id) {
+                Some(t)
+                    if matches!(
+                        btf_kind(t),
+                        BTF_KIND_TYPEDEF
+                            | BTF_KIND_VOLATILE
+                            | BTF_KIND_CONST
+                            | BTF_KIND_RESTRICT
+                            | BTF_KIND_TYPE_TAG
+                    ) =>
+                {
+                    type_id = unsafe { t.__bindgen_anon_1.type_ };
+                }
+                _ => break,
+            }
+        }
+        type_id
+    }
+}
+
+fn btf_kind(t: &btf_type) -> u32 {
+    (t.info >> 24) & 0x1f
+}
+
+fn btf_vlen(t: &btf_type) -> usize {
+    (t.info & 0xffff) as usize
+}
+
+fn btf_kflag(t: &btf_type) -> bool {
+    t.info >> 31 == 1
+}
+
+// Kind-specific data (members, enumerators, ...) is laid out right after the type.
+unsafe fn trailing<T>(t: &btf_type) -> *const T {
+    unsafe { (t as *const btf_type).add(1) as *const T }
+}
+
+fn read_bits(data: &[u8], bit_offset: usize, bits: usize) -> u128 {
+    (0..bits.min(128)).fold(0, |acc, i| {
+        let bit = bit_offset + i;
+        let set = data.get(bit / 8).is_some_and(|b| (b >> (bit % 8)) & 1 == 1);
+        acc | ((set as u128) << i)
+    })
+}
+
+fn write_bits(data: &mut [u8], bit_offset: usize, bits: usize, value: u128) {
+    for i in 0..bits.min(128) {
+        let bit = bit_offset + i;
+        if let Some(b) = data.get_mut(bit / 8) {
+            let mask = 1 << (bit % 8);
+            if (value >> i) & 1 == 1 {
+                *b |= mask;
+            } else {
+                *b &= !mask;
+            }
+        }
+    }
+}
+
+fn sign_extend(raw: u128, bits: usize) -> i128 {
+    if bits == 0 || bits >= 128 {
+        return raw as i128;
+    }
+    let shift = 128 - bits;
+    ((raw << shift) as i128) >> shift
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{self, ErrorKind, Write},
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt},
+    process::{self, Command},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use libbpf_sys::{BPF_MAP_TYPE_PROG_ARRAY, bpf_prog_info};
+
+use crate::{
+    helpers::{self, btf::Btf},
+    utils::{
+        self,
+        disasm::{BPF_PSEUDO_CALL, BPF_PSEUDO_KFUNC_CALL, Insn, Symbols},
+    },
+};
+
+/// Kernel symbols by address, from /proc/kallsyms. Addresses read as zero without
+/// CAP_SYSLOG, in which case nothing resolves.
+pub struct Kallsyms {
+    symbols: HashMap<u64, String>,
+    // Helper and kfunc call immediates are relative to this symbol.
+    call_base: Option<u64>,
+}
+
+impl Kallsyms {
+    pub fn load() -> Self {
+        let mut symbols = HashMap::new();
+        let mut call_base = None;
+        let text = fs::read_to_string("/proc/kallsyms").unwrap_or_default();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(addr), Some(name)) = (fields.next(), fields.nth(1)) else {
+                continue;
+            };
+            let Ok(addr) = u64::from_str_radix(addr, 16) else {
+                continue;
+            };
+            if addr == 0 {
+                continue;
+            }
+            if name == "__bpf_call_base" {
+                call_base = Some(addr);
+            }
+            symbols.entry(addr).or_insert_with(|| name.to_string());
+        }
+        Self { symbols, call_base }
+    }
+
+    pub fn name(&self, addr: u64) -> Option<&str> {
+        self.symbols.get(&addr).map(String::as_str)
+    }
+}
+
+/// Strips the `bpf_prog_<tag>_` prefix the kernel gives JITed functions.
+fn prog_func_name(symbol: &str) -> &str {
+    symbol
+        .strip_prefix("bpf_prog_")
+        .and_then(|rest| rest.get(16..))
+        .and_then(|rest| rest.strip_prefix('_'))
+        .unwrap_or(symbol)
+}
+
+/// Resolves what `prog`'s instructions refer to: helpers and kfuncs through kallsyms or
+/// vmlinux BTF, maps and tail-call slots through their map IDs, subprograms through the
+/// JITed symbols, and source lines through line_info and the program's BTF.
+pub fn fetch_symbols(prog: &bpf_prog_info, insns: &[Insn]) -> Symbols {
+    let kallsyms = Kallsyms::load();
+    let mut symbols = Symbols::default();
+
+    let calls = insns
+        .iter()
+        .filter(|insn| insn.is_call() && insn.src != BPF_PSEUDO_CALL)
+        .collect::<Vec<_>>();
+    if let Some(base) = kallsyms.call_base {
+        for insn in &calls {
+            let addr = base.wrapping_add_signed(insn.imm as i64);
+            if let Some(name) = kallsyms.name(addr) {
+                symbols.calls.insert(insn.imm, name.to_string());
+            }
+        }
+    }
+    let kfuncs = calls
+        .iter()
+        .filter(|insn| insn.src == BPF_PSEUDO_KFUNC_CALL && !symbols.calls.contains_key(&insn.imm))
+        .collect::<Vec<_>>();
+    // vmlinux BTF is several megabytes, so only load it when there is a kfunc to name.
+    if let Some(btf) = (!kfuncs.is_empty()).then(Btf::load_vmlinux).flatten() {
+        for insn in kfuncs {
+            if let Some(name) = btf.func_name(insn.imm as u32) {
+                symbols.kfuncs.insert(insn.imm, name);
+            }
+        }
+    }
+
+    symbols.subprogs = helpers::programs::get_jited_ksyms(prog)
+        .into_iter()
+        .map(|addr| {
+            kallsyms
+                .name(addr)
+                .map(|name| prog_func_name(name).to_string())
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+
+    for map_id in helpers::programs::get_map_ids_by_prog(prog) {
+        let map = helpers::maps::fetch_maps_by_id(map_id);
+        if map.id != map_id {
+            continue;
+        }
+        let name = utils::programs::cstring_from_i8_array(map.name).unwrap_or_default();
+        symbols.maps.insert(map_id, name);
+        if map.type_ != BPF_MAP_TYPE_PROG_ARRAY {
+            continue;
+        }
+        let slots = helpers::maps::fetch_map_entries(&map)
+            .iter()
+            .filter_map(|entry| {
+                let slot = u32::from_ne_bytes(*entry.key.first_chunk::<4>()?);
+                let prog_id = u32::from_ne_bytes(*entry.values.first()?.first_chunk::<4>()?);
+                let target = helpers::programs::fetch_programs_by_id(prog_id);
+                let name = utils::programs::cstring_from_i8_array(target.name)
+                    .unwrap_or_else(|| "N/A".into());
+                Some((slot, format!("#{} {}", prog_id, name)))
+            })
+            .collect();
+        symbols.tail_calls.insert(map_id, slots);
+    }
+
+    if let Some(btf) = Btf::load_by_id(prog.btf_id) {
+        for line in helpers::programs::get_line_info(prog) {
+            let comment = utils::disasm::source_comment(
+                &btf.name(line.line_off),
+                &btf.name(line.file_name_off),
+                line.line_col,
+            );
+            symbols
+                .source
+                .entry(line.insn_off as usize)
+                .or_default()
+                .push(comment);
+        }
+    }
+    symbols
+}
+
+/// Disassembles the program's JITed image one function at a time, with source lines placed
+/// at the native address line_info maps them to.
+pub fn disassemble_jited(prog: &bpf_prog_info) -> Vec<String> {
+    let image = helpers::programs::get_jited_insns(prog);
+    if image.is_empty() {
+        return vec!["no JITed image available (not JITed, or needs CAP_SYS_ADMIN)".to_string()];
+    }
+    let kallsyms = Kallsyms::load();
+    let ksyms = helpers::programs::get_jited_ksyms(prog);
+    let mut lens = helpers::programs::get_jited_func_lens(prog);
+    if lens.is_empty() {
+        lens.push(image.len() as u32);
+    }
+
+    let mut source: HashMap<u64, Vec<String>> = HashMap::new();
+    if let Some(btf) = Btf::load_by_id(prog.btf_id) {
+        let line_info = helpers::programs::get_line_info(prog);
+        let addrs = helpers::programs::get_jited_line_info(prog);
+        for (line, addr) in line_info.iter().zip(addrs) {
+            let comment = utils::disasm::source_comment(
+                &btf.name(line.line_off),
+                &btf.name(line.file_name_off),
+                line.line_col,
+            );
+            source
+                .entry(addr)
+                .or_default()
+                .push(format!("{} (insn {})", comment, line.insn_off));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, len) in lens.into_iter().enumerate() {
+        let Some(code) = image.get(start..start + len as usize) else {
+            break;
+        };
+        start += len as usize;
+        let addr = ksyms.get(i).copied().unwrap_or_default();
+        let name = kallsyms
+            .name(addr)
+            .map_or_else(|| format!("func_{}", i), |n| prog_func_name(n).to_string());
+        lines.push(format!("{}:", name));
+
+        match objdump(code) {
+            Ok(insns) => {
+                for (offset, text) in insns {
+                    let comments = source.get(&(addr + offset));
+                    lines.extend(comments.into_iter().flatten().cloned());
+                    lines.push(format!("{:4x}:  {}", offset, text));
+                }
+            }
+            Err(e) => {
+                lines.push(format!("; cannot disassemble: {}", e));
+                lines.extend(code.chunks(16).enumerate().map(|(row, bytes)| {
+                    let hex = bytes.iter().map(|b| format!("{:02x}", b));
+                    format!("{:4x}:  {}", row * 16, hex.collect::<Vec<_>>().join(" "))
+                }));
+            }
+        }
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Runs the host's objdump (from binutils) over raw machine code and returns
+/// `(offset, "bytes  insn")` pairs. Bytes of long instructions that objdump wraps onto a
+/// second line are dropped.
+fn objdump(code: &[u8]) -> io::Result<Vec<(u64, String)>> {
+    let arch = match env::consts::ARCH {
+        "x86_64" => "i386:x86-64",
+        "aarch64" => "aarch64",
+        other => {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("no disassembler for {}", other),
+            ));
+        }
+    };
+    // eman runs as root, so the image goes into a fresh directory only root can enter,
+    // never through a path someone else may have planted a symlink at.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let dir = env::temp_dir().join(format!("eman-jit-{}-{}", process::id(), nanos));
+    DirBuilder::new().mode(0o700).create(&dir)?;
+    let path = dir.join("image.bin");
+    let output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(code))
+        .and_then(|()| {
+            Command::new("objdump")
+                .args(["-D", "-b", "binary", "-m", arch])
+                .arg(&path)
+                .output()
+                .map_err(|e| match e.kind() {
+                    ErrorKind::NotFound => io::Error::new(
+                        ErrorKind::NotFound,
+                        "objdump not found in PATH, install binutils to disassemble JITed code",
+                    ),
+                    _ => e,
+                })
+        });
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_dir(&dir);
+    let output = output?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(stderr.trim().to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (offset, rest) = line.split_once(":\t")?;
+            let offset = u64::from_str_radix(offset.trim(), 16).ok()?;
+            let (bytes, insn) = rest.split_once('\t')?;
+            Some((offset, format!("{:<24}{}", bytes.trim_end(), insn)))
+        })
+        .collect())
+}
@@ -0,0 +1,686 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use libbpf_sys::{BPF_ANY, bpf_map_info};
+use serde_json::{Value, json};
+
+use crate::{
+    helpers::{
+        btf::Btf,
+        maps::{self, MapEntry},
+    },
+    utils,
+};
+
+// Binary dumps start with this magic followed by a little-endian format version.
+const BINARY_MAGIC: &[u8; 8] = b"EMANMAP\0";
+const BINARY_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Json,
+    Csv,
+    Binary,
+}
+
+impl DumpFormat {
+    pub const ALL: [DumpFormat; 3] = [DumpFormat::Json, DumpFormat::Csv, DumpFormat::Binary];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            DumpFormat::Json => "json",
+            DumpFormat::Csv => "csv",
+            DumpFormat::Binary => "bin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(DumpFormat::Json),
+            "csv" => Some(DumpFormat::Csv),
+            "bin" | "binary" => Some(DumpFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+pub fn default_dump_path(info: &bpf_map_info, format: DumpFormat) -> String {
+    let name = utils::programs::cstring_from_i8_array(info.name).unwrap_or_default();
+    format!("map-{}-{}.{}", info.id, name, format.extension())
+}
+
+/// Writes every entry of the map to `path`, returning how many entries were written.
+pub fn export_map(info: &bpf_map_info, path: &Path, format: DumpFormat) -> io::Result<usize> {
+    let entries = maps::fetch_map_entries(info);
+    let btf = Btf::load_by_id(info.btf_id);
+    let mut out = BufWriter::new(File::create(path)?);
+
+    match format {
+        DumpFormat::Json => write_json(&mut out, info, &entries, btf.as_ref())?,
+        DumpFormat::Csv => write_csv(&mut out, info, &entries, btf.as_ref())?,
+        DumpFormat::Binary => write_binary(&mut out, info, &entries)?,
+    }
+    out.flush()?;
+    Ok(entries.len())
+}
+
+fn metadata_json(info: &bpf_map_info, values_per_entry: usize) -> Value {
+    json!({
+        "id": info.id,
+        "name": utils::programs::cstring_from_i8_array(info.name).unwrap_or_default(),
+        "type": utils::maps::bpf_map_type_to_str(info.type_),
+        "type_id": info.type_,
+        "key_size": info.key_size,
+        "value_size": info.value_size,
+        "max_entries": info.max_entries,
+        "map_flags": info.map_flags,
+        "ifindex": info.ifindex,
+        "btf_id": info.btf_id,
+        "btf_key_type_id": info.btf_key_type_id,
+        "btf_value_type_id": info.btf_value_type_id,
+        "values_per_entry": values_per_entry,
+        "exported_at": chrono::Local::now().to_rfc3339(),
+    })
+}
+
+fn decoded_json(btf: Option<&Btf>, type_id: u32, bytes: &[u8]) -> Value {
+    match btf {
+        Some(btf) if type_id != 0 => utils::btf::to_json(&btf.decode(type_id, bytes)),
+        _ => Value::from(utils::maps::hex_string(bytes)),
+    }
+}
+
+fn values_per_entry(info: &bpf_map_info, entries: &[MapEntry]) -> usize {
+    entries.first().map_or_else(
+        || {
+            if utils::maps::is_percpu_map(info.type_) {
+                maps::num_possible_cpus()
+            } else {
+                1
+            }
+        },
+        |e| e.values.len(),
+    )
+}
+
+fn write_json(
+    out: &mut impl Write,
+    info: &bpf_map_info,
+    entries: &[MapEntry],
+    btf: Option<&Btf>,
+) -> io::Result<()> {
+    let percpu = utils::maps::is_percpu_map(info.type_);
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let values = entry
+                .values
+                .iter()
+                .map(|v| decoded_json(btf, info.btf_value_type_id, v))
+                .collect::<Vec<_>>();
+            let raw_values = entry
+                .values
+                .iter()
+                .map(|v| Value::from(utils::maps::hex_string(v)))
+                .collect::<Vec<_>>();
+            let (value, raw_value) = if percpu {
+                (Value::Array(values), Value::Array(raw_values))
+            } else {
+                (
+                    values.into_iter().next().unwrap_or(Value::Null),
+                    raw_values.into_iter().next().unwrap_or(Value::Null),
+                )
+            };
+            json!({
+                "key": decoded_json(btf, info.btf_key_type_id, &entry.key),
+                "value": value,
+                "raw_key": utils::maps::hex_string(&entry.key),
+                "raw_value": raw_value,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let dump = json!({
+        "map": metadata_json(info, values_per_entry(info, entries)),
+        "entries": rows,
+    });
+    serde_json::to_writer_pretty(&mut *out, &dump)?;
+    writeln!(out)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_csv(
+    out: &mut impl Write,
+    info: &bpf_map_info,
+    entries: &[MapEntry],
+    btf: Option<&Btf>,
+) -> io::Result<()> {
+    // Metadata goes first as `# key=value` comment lines.
+    if let Value::Object(meta) = metadata_json(info, values_per_entry(info, entries)) {
+        for (k, v) in meta {
+            let v = v.as_str().map_or_else(|| v.to_string(), String::from);
+            writeln!(out, "# {}={}", k, v)?;
+        }
+    }
+
+    let nvalues = values_per_entry(info, entries);
+    let mut header = vec!["key".to_string()];
+    if nvalues == 1 {
+        header.push("value".to_string());
+    } else {
+        header.extend((0..nvalues).map(|cpu| format!("value_cpu{cpu}")));
+    }
+    if btf.is_some() {
+        header.push("key_decoded".to_string());
+        header.push("value_decoded".to_string());
+    }
+    writeln!(out, "{}", header.join(","))?;
+
+    for entry in entries {
+        let mut row = vec![utils::maps::hex_string(&entry.key)];
+        row.extend(entry.values.iter().map(|v| utils::maps::hex_string(v)));
+        if let Some(btf) = btf {
+            let show =
+                |type_id: u32, bytes: &[u8]| utils::btf::format_inline(&btf.decode(type_id, bytes));
+            row.push(show(info.btf_key_type_id, &entry.key));
+            row.push(
+                entry
+                    .values
+                    .iter()
+                    .map(|v| show(info.btf_value_type_id, v))
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+        }
+        let row = row.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Layout: magic, version, then length-prefixed metadata and entries, all little-endian.
+/// Each entry is `u32 key_len, key, u32 value_len, value` with per-CPU values concatenated.
+fn write_binary(out: &mut impl Write, info: &bpf_map_info, entries: &[MapEntry]) -> io::Result<()> {
+    let mut meta = Vec::new();
+    for field in [
+        info.type_,
+        info.id,
+        info.key_size,
+        info.value_size,
+        info.max_entries,
+        info.map_flags,
+        info.btf_id,
+        info.btf_key_type_id,
+        info.btf_value_type_id,
+        values_per_entry(info, entries) as u32,
+    ] {
+        meta.extend_from_slice(&field.to_le_bytes());
+    }
+    meta.extend(info.name.iter().map(|&c| c as u8));
+
+    out.write_all(BINARY_MAGIC)?;
+    out.write_all(&BINARY_VERSION.to_le_bytes())?;
+    write_chunk(out, &meta)?;
+    out.write_all(&(entries.len() as u32).to_le_bytes())?;
+    for entry in entries {
+        write_chunk(out, &entry.key)?;
+        write_chunk(out, &entry.values.concat())?;
+    }
+    Ok(())
+}
+
+fn write_chunk(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    out.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    out.write_all(bytes)
+}
+
+pub struct Dump {
+    pub key_size: u32,
+    pub value_size: u32,
+    pub entries: Vec<MapEntry>,
+}
+
+pub struct ImportPlan {
+    pub added: Vec<MapEntry>,
+    pub changed: Vec<MapEntry>,
+    pub removed: Vec<Vec<u8>>,
+    pub unchanged: usize,
+}
+
+impl ImportPlan {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads a dump written by `export_map`; the format is detected from the contents.
+pub fn read_dump(path: &Path) -> io::Result<Dump> {
+    parse_dump(&fs::read(path)?)
+}
+
+fn parse_dump(data: &[u8]) -> io::Result<Dump> {
+    if data.starts_with(BINARY_MAGIC) {
+        read_binary(data)
+    } else if data.trim_ascii_start().starts_with(b"{") {
+        read_json(data)
+    } else {
+        read_csv(&String::from_utf8_lossy(data))
+    }
+}
+
+fn hex_field(value: &Value, what: &str) -> io::Result<Vec<u8>> {
+    value
+        .as_str()
+        .ok_or_else(|| invalid(format!("{} is not a hex string", what)))
+        .and_then(|s| utils::maps::parse_hex_bytes(s).map_err(invalid))
+}
+
+fn read_json(data: &[u8]) -> io::Result<Dump> {
+    let dump: Value = serde_json::from_slice(data)?;
+    let size = |field: &str| {
+        dump["map"][field]
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or_else(|| invalid(format!("missing map.{}", field)))
+    };
+    let key_size = size("key_size")?;
+    let value_size = size("value_size")?;
+
+    let entries = dump["entries"]
+        .as_array()
+        .ok_or_else(|| invalid("missing entries"))?
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let key = hex_field(&entry["raw_key"], &format!("entries[{i}].raw_key"))?;
+            let what = format!("entries[{i}].raw_value");
+            let values = match &entry["raw_value"] {
+                Value::Array(values) => values
+                    .iter()
+                    .map(|v| hex_field(v, &what))
+                    .collect::<io::Result<Vec<_>>>()?,
+                value => vec![hex_field(value, &what)?],
+            };
+            Ok(MapEntry { key, values })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(Dump {
+        key_size,
+        value_size,
+        entries,
+    })
+}
+
+fn csv_split(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn read_csv(text: &str) -> io::Result<Dump> {
+    let mut meta = HashMap::new();
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = loop {
+        match lines.next() {
+            Some(line) => match line.strip_prefix('#') {
+                Some(comment) => {
+                    if let Some((k, v)) = comment.trim().split_once('=') {
+                        meta.insert(k.to_string(), v.to_string());
+                    }
+                }
+                None => break csv_split(line),
+            },
+            None => return Err(invalid("missing CSV header")),
+        }
+    };
+    let size = |field: &str| {
+        meta.get(field)
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or_else(|| invalid(format!("missing `# {}=` header", field)))
+    };
+    let key_size = size("key_size")?;
+    let value_size = size("value_size")?;
+
+    let key_col = header.iter().position(|h| h == "key");
+    let value_cols = header
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| *h == "value" || h.starts_with("value_cpu"))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let Some(key_col) = key_col.filter(|_| !value_cols.is_empty()) else {
+        return Err(invalid("CSV header needs key and value columns"));
+    };
+
+    let entries = lines
+        .enumerate()
+        .map(|(n, line)| {
+            let fields = csv_split(line);
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .ok_or_else(|| invalid(format!("row {} is missing columns", n + 1)))
+                    .and_then(|f| utils::maps::parse_hex_bytes(f).map_err(invalid))
+            };
+            Ok(MapEntry {
+                key: field(key_col)?,
+                values: value_cols
+                    .iter()
+                    .map(|&i| field(i))
+                    .collect::<io::Result<Vec<_>>>()?,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(Dump {
+        key_size,
+        value_size,
+        entries,
+    })
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let chunk = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid("truncated binary dump"))?;
+        self.pos += len;
+        Ok(chunk)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn chunk(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+fn read_binary(data: &[u8]) -> io::Result<Dump> {
+    let mut reader = BinaryReader {
+        data,
+        pos: BINARY_MAGIC.len(),
+    };
+    let version = reader.u32()?;
+    if version != BINARY_VERSION {
+        return Err(invalid(format!("unsupported dump version {}", version)));
+    }
+
+    let mut meta = BinaryReader {
+        data: reader.chunk()?,
+        pos: 0,
+    };
+    // type, id, then the sizes; see `write_binary` for the full layout.
+    meta.take(8)?;
+    let key_size = meta.u32()?;
+    let value_size = meta.u32()?;
+    meta.take(20)?;
+    let values_per_entry = meta.u32()? as usize;
+
+    let count = reader.u32()?;
+    let entries = (0..count)
+        .map(|_| {
+            let key = reader.chunk()?.to_vec();
+            let values = reader.chunk()?;
+            let values = if values_per_entry > 1 {
+                values
+                    .chunks(value_size.max(1) as usize)
+                    .map(<[u8]>::to_vec)
+                    .collect()
+            } else {
+                vec![values.to_vec()]
+            };
+            Ok(MapEntry { key, values })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(Dump {
+        key_size,
+        value_size,
+        entries,
+    })
+}
+
+/// Checks a dump against the live map and works out what importing it would change.
+/// Keys missing from the dump are scheduled for removal, except in array maps.
+pub fn plan_import(info: &bpf_map_info, dump: &Dump) -> io::Result<ImportPlan> {
+    if dump.key_size != info.key_size || dump.value_size != info.value_size {
+        return Err(invalid(format!(
+            "dump has key/value size {}/{}, map #{} has {}/{}",
+            dump.key_size, dump.value_size, info.id, info.key_size, info.value_size
+        )));
+    }
+    let ncpus = if utils::maps::is_percpu_map(info.type_) {
+        maps::num_possible_cpus()
+    } else {
+        1
+    };
+    for entry in &dump.entries {
+        if entry.key.len() != info.key_size as usize
+            || entry
+                .values
+                .iter()
+                .any(|v| v.len() != info.value_size as usize)
+        {
+            return Err(invalid(format!(
+                "entry {} does not match the map's key/value size",
+                utils::maps::hex_string(&entry.key)
+            )));
+        }
+        if entry.values.len() != 1 && entry.values.len() != ncpus {
+            return Err(invalid(format!(
+                "entry {} has {} values, map expects 1 or {}",
+                utils::maps::hex_string(&entry.key),
+                entry.values.len(),
+                ncpus
+            )));
+        }
+    }
+
+    let current = maps::fetch_map_entries(info)
+        .into_iter()
+        .map(|e| (e.key, e.values))
+        .collect::<HashMap<_, _>>();
+    let mut plan = ImportPlan {
+        added: Vec::new(),
+        changed: Vec::new(),
+        removed: Vec::new(),
+        unchanged: 0,
+    };
+    for entry in &dump.entries {
+        let new = MapEntry {
+            key: entry.key.clone(),
+            values: entry.values.clone(),
+        };
+        match current.get(&entry.key) {
+            None => plan.added.push(new),
+            Some(values) if same_values(values, &entry.values) => plan.unchanged += 1,
+            Some(_) => plan.changed.push(new),
+        }
+    }
+    if !utils::maps::is_array_map(info.type_) {
+        let incoming = dump
+            .entries
+            .iter()
+            .map(|e| e.key.as_slice())
+            .collect::<HashSet<_>>();
+        plan.removed = current
+            .into_keys()
+            .filter(|k| !incoming.contains(k.as_slice()))
+            .collect();
+    }
+    Ok(plan)
+}
+
+fn same_values(current: &[Vec<u8>], new: &[Vec<u8>]) -> bool {
+    current == new || (new.len() == 1 && current.iter().all(|v| *v == new[0]))
+}
+
+pub fn describe_plan(info: &bpf_map_info, plan: &ImportPlan, btf: Option<&Btf>) -> Vec<String> {
+    let key = |bytes: &[u8]| match btf {
+        Some(btf) if info.btf_key_type_id != 0 => {
+            utils::btf::format_inline(&btf.decode(info.btf_key_type_id, bytes))
+        }
+        _ => utils::maps::hex_string(bytes),
+    };
+    let mut lines = vec![format!(
+        "{} added, {} changed, {} removed, {} unchanged",
+        plan.added.len(),
+        plan.changed.len(),
+        plan.removed.len(),
+        plan.unchanged
+    )];
+    lines.extend(plan.added.iter().map(|e| format!("+ {}", key(&e.key))));
+    lines.extend(plan.changed.iter().map(|e| format!("~ {}", key(&e.key))));
+    lines.extend(plan.removed.iter().map(|k| format!("- {}", key(k))));
+    lines
+}
+
+/// Applies a plan, carrying on past individual failures and returning their descriptions.
+pub fn apply_import(info: &bpf_map_info, plan: &ImportPlan) -> (usize, Vec<String>) {
+    let mut applied = 0;
+    let mut failures = Vec::new();
+    for entry in plan.added.iter().chain(&plan.changed) {
+        match maps::update_map_entry(info, &entry.key, &entry.values, BPF_ANY as u64) {
+            Ok(()) => applied += 1,
+            Err(e) => failures.push(format!(
+                "update {}: {}",
+                utils::maps::hex_string(&entry.key),
+                e
+            )),
+        }
+    }
+    for key in &plan.removed {
+        match maps::delete_map_entry(info, key) {
+            Ok(()) => applied += 1,
+            Err(e) => failures.push(format!("delete {}: {}", utils::maps::hex_string(key), e)),
+        }
+    }
+    (applied, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::*;
+
+    fn map_info(type_: u32, key_size: u32, value_size: u32) -> bpf_map_info {
+        let mut info: bpf_map_info = unsafe { mem::zeroed() };
+        info.type_ = type_;
+        info.id = 7;
+        info.key_size = key_size;
+        info.value_size = value_size;
+        info.max_entries = 16;
+        for (dst, src) in info.name.iter_mut().zip(b"counters") {
+            *dst = *src as _;
+        }
+        info
+    }
+
+    fn write(info: &bpf_map_info, entries: &[MapEntry], format: DumpFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+        match format {
+            DumpFormat::Json => write_json(&mut out, info, entries, None),
+            DumpFormat::Csv => write_csv(&mut out, info, entries, None),
+            DumpFormat::Binary => write_binary(&mut out, info, entries),
+        }
+        .unwrap();
+        out
+    }
+
+    fn assert_round_trip(info: &bpf_map_info, entries: &[MapEntry]) {
+        for format in DumpFormat::ALL {
+            let dump = parse_dump(&write(info, entries, format)).unwrap();
+            let name = format.extension();
+            assert_eq!(dump.key_size, info.key_size, "{name}");
+            assert_eq!(dump.value_size, info.value_size, "{name}");
+            assert_eq!(dump.entries.len(), entries.len(), "{name}");
+            for (read, written) in dump.entries.iter().zip(entries) {
+                assert_eq!(read.key, written.key, "{name}");
+                assert_eq!(read.values, written.values, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            MapEntry {
+                key: vec![1, 0, 0, 0],
+                values: vec![vec![0xde, 0xad, 0xbe, 0xef, 0, 0, 0, 1]],
+            },
+            MapEntry {
+                key: vec![0xff, 0xff, 0xff, 0xff],
+                values: vec![vec![0; 8]],
+            },
+        ];
+        assert_round_trip(&map_info(1, 4, 8), &entries);
+    }
+
+    #[test]
+    fn round_trip_per_cpu() {
+        let entries = [MapEntry {
+            key: vec![2, 0, 0, 0],
+            values: vec![vec![1, 0, 0, 0], vec![2, 0, 0, 0], vec![3, 0, 0, 0]],
+        }];
+        assert_round_trip(&map_info(6, 4, 4), &entries);
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        // The sizes still have to come through with no entry to infer them from.
+        assert_round_trip(&map_info(1, 4, 8), &[]);
+    }
+
+    #[test]
+    fn rejects_damaged_dumps() {
+        let info = map_info(1, 4, 8);
+        let entries = [MapEntry {
+            key: vec![1, 0, 0, 0],
+            values: vec![vec![0; 8]],
+        }];
+        let binary = write(&info, &entries, DumpFormat::Binary);
+        assert!(parse_dump(&binary[..binary.len() - 1]).is_err());
+        assert!(parse_dump(b"key,value\n01,02\n").is_err());
+        assert!(parse_dump(b"{\"entries\": []}").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::{BTreeMap, HashSet};
+
+use libbpf_sys::{BPF_MAP_TYPE_PROG_ARRAY, bpf_map_info, bpf_prog_info};
+
+use crate::{helpers, utils};
+
+/// Which programs reference which PROG_ARRAY maps, and which programs sit in their slots.
+pub struct TailCallGraph {
+    programs: BTreeMap<u32, bpf_prog_info>,
+    prog_arrays: BTreeMap<u32, bpf_map_info>,
+    // Program ID → IDs of the PROG_ARRAY maps it references.
+    uses: BTreeMap<u32, Vec<u32>>,
+    // PROG_ARRAY map ID → (slot, program ID) pairs.
+    slots: BTreeMap<u32, Vec<(u32, u32)>>,
+}
+
+pub fn fetch_tail_call_graph() -> TailCallGraph {
+    let programs = helpers::programs::fetch_programs()
+        .into_iter()
+        .map(|prog| (prog.id, prog))
+        .collect::<BTreeMap<_, _>>();
+    let prog_arrays = helpers::maps::fetch_maps()
+        .into_iter()
+        .filter(|map| map.type_ == BPF_MAP_TYPE_PROG_ARRAY)
+        .map(|map| (map.id, map))
+        .collect::<BTreeMap<_, _>>();
+
+    let uses = programs
+        .values()
+        .map(|prog| {
+            let maps = helpers::programs::get_map_ids_by_prog(prog)
+                .into_iter()
+                .filter(|id| prog_arrays.contains_key(id))
+                .collect::<Vec<_>>();
+            (prog.id, maps)
+        })
+        .filter(|(_, maps)| !maps.is_empty())
+        .collect();
+    let slots = prog_arrays
+        .values()
+        .map(|map| {
+            let slots = helpers::maps::fetch_map_entries(map)
+                .iter()
+                .filter_map(|entry| {
+                    let slot = u32::from_ne_bytes(*entry.key.first_chunk::<4>()?);
+                    let prog = u32::from_ne_bytes(*entry.values.first()?.first_chunk::<4>()?);
+                    Some((slot, prog))
+                })
+                .collect();
+            (map.id, slots)
+        })
+        .collect();
+
+    TailCallGraph {
+        programs,
+        prog_arrays,
+        uses,
+        slots,
+    }
+}
+
+impl TailCallGraph {
+    fn prog_label(&self, id: u32) -> String {
+        match self.programs.get(&id) {
+            Some(prog) => format!(
+                "{} (#{}, {})",
+                utils::programs::cstring_from_i8_array(prog.name).unwrap_or_else(|| "N/A".into()),
+                id,
+                utils::programs::bpf_prog_type_to_str(prog.type_)
+            ),
+            None => format!("#{} (not loaded)", id),
+        }
+    }
+
+    fn map_label(&self, id: u32) -> String {
+        let name = self
+            .prog_arrays
+            .get(&id)
+            .and_then(|map| utils::programs::cstring_from_i8_array(map.name))
+            .unwrap_or_else(|| "N/A".into());
+        format!("{} (#{}, PROG_ARRAY)", name, id)
+    }
+
+    /// Renders the graph as an indented tree, starting from the programs that use a
+    /// PROG_ARRAY without being a tail-call target themselves.
+    pub fn tree(&self) -> Vec<String> {
+        let targets = self
+            .slots
+            .values()
+            .flatten()
+            .map(|&(_, prog)| prog)
+            .collect::<HashSet<_>>();
+        let mut shown = HashSet::new();
+        let mut lines = Vec::new();
+
+        for &id in self.uses.keys().filter(|id| !targets.contains(id)) {
+            self.push_program(id, "", 0, &mut shown, &mut lines);
+        }
+        // Programs only reachable from each other, i.e. tail-call cycles.
+        for &id in self.uses.keys() {
+            if !shown.contains(&id) {
+                self.push_program(id, "", 0, &mut shown, &mut lines);
+            }
+        }
+        // PROG_ARRAYs no loaded program references, e.g. kept alive by a pin.
+        for &map_id in self.prog_arrays.keys() {
+            if !self.uses.values().flatten().any(|&id| id == map_id) {
+                self.push_map(map_id, 0, &mut shown, &mut lines);
+            }
+        }
+
+        if lines.is_empty() {
+            lines.push("no PROG_ARRAY maps or tail calls found".to_string());
+        }
+        lines
+    }
+
+    fn push_program(
+        &self,
+        id: u32,
+        prefix: &str,
+        depth: usize,
+        shown: &mut HashSet<u32>,
+        lines: &mut Vec<String>,
+    ) {
+        let indent = "    ".repeat(depth);
+        if !shown.insert(id) {
+            lines.push(format!("{indent}{prefix}{} ↺", self.prog_label(id)));
+            return;
+        }
+        lines.push(format!("{indent}{prefix}{}", self.prog_label(id)));
+        for &map_id in self.uses.get(&id).into_iter().flatten() {
+            self.push_map(map_id, depth + 1, shown, lines);
+        }
+    }
+
+    fn push_map(&self, id: u32, depth: usize, shown: &mut HashSet<u32>, lines: &mut Vec<String>) {
+        lines.push(format!("{}└─ {}", "    ".repeat(depth), self.map_label(id)));
+        let slots = self.slots.get(&id).map_or(&[][..], Vec::as_slice);
+        if slots.is_empty() {
+            lines.push(format!("{}(empty)", "    ".repeat(depth + 1)));
+        }
+        for &(slot, prog) in slots {
+            self.push_program(prog, &format!("[{slot}] → "), depth + 1, shown, lines);
+        }
+    }
+
+    /// Renders the graph in Graphviz DOT: programs are boxes, PROG_ARRAYs cylinders, and
+    /// slot edges are labelled with their index.
+    pub fn to_dot(&self) -> String {
+        let mut nodes = BTreeMap::new();
+        let mut edges = Vec::new();
+        for (&prog, maps) in &self.uses {
+            nodes.insert(format!("prog_{prog}"), self.prog_node(prog));
+            for &map in maps {
+                edges.push(format!("  prog_{prog} -> map_{map};"));
+            }
+        }
+        for (&map, slots) in &self.slots {
+            nodes.insert(format!("map_{map}"), self.map_node(map));
+            for &(slot, prog) in slots {
+                nodes.insert(format!("prog_{prog}"), self.prog_node(prog));
+                edges.push(format!("  map_{map} -> prog_{prog} [label=\"{slot}\"];"));
+            }
+        }
+
+        let mut dot = String::from("digraph tail_calls {\n  rankdir=LR;\n");
+        for (node, attrs) in nodes {
+            dot += &format!("  {node} {attrs};\n");
+        }
+        for edge in edges {
+            dot += &edge;
+            dot.push('\n');
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn prog_node(&self, id: u32) -> String {
+        format!(
+            "[shape=box, label=\"{}\"]",
+            dot_escape(&self.prog_label(id))
+        )
+    }
+
+    fn map_node(&self, id: u32) -> String {
+        format!(
+            "[shape=cylinder, label=\"{}\"]",
+            dot_escape(&self.map_label(id))
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Mermaid => "mmd",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "mermaid" | "mmd" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+/// Programs and maps as nodes, with an edge for every map a program uses.
+pub struct UsageGraph {
+    programs: BTreeMap<u32, bpf_prog_info>,
+    maps: BTreeMap<u32, bpf_map_info>,
+    // (program ID, map ID)
+    edges: Vec<(u32, u32)>,
+}
+
+pub fn fetch_usage_graph() -> UsageGraph {
+    let programs = helpers::programs::fetch_programs()
+        .into_iter()
+        .map(|prog| (prog.id, prog))
+        .collect::<BTreeMap<_, _>>();
+    let maps = helpers::maps::fetch_maps()
+        .into_iter()
+        .map(|map| (map.id, map))
+        .collect::<BTreeMap<_, _>>();
+    let edges = programs
+        .values()
+        .flat_map(|prog| {
+            helpers::programs::get_map_ids_by_prog(prog)
+                .into_iter()
+                .map(|map_id| (prog.id, map_id))
+        })
+        .collect();
+    UsageGraph {
+        programs,
+        maps,
+        edges,
+    }
+}
+
+impl UsageGraph {
+    /// Keeps only `prog_id`, the maps it uses, and the other programs using those maps.
+    pub fn neighborhood(mut self, prog_id: u32) -> Self {
+        let maps = self
+            .edges
+            .iter()
+            .filter(|&&(prog, _)| prog == prog_id)
+            .map(|&(_, map)| map)
+            .collect::<HashSet<_>>();
+        self.edges.retain(|(_, map)| maps.contains(map));
+        let programs = self
+            .edges
+            .iter()
+            .map(|&(prog, _)| prog)
+            .chain([prog_id])
+            .collect::<HashSet<_>>();
+        self.programs.retain(|id, _| programs.contains(id));
+        self.maps.retain(|id, _| maps.contains(id));
+        self
+    }
+
+    fn prog_label(&self, id: u32) -> (String, String) {
+        match self.programs.get(&id) {
+            Some(prog) => (
+                utils::programs::cstring_from_i8_array(prog.name).unwrap_or_else(|| "N/A".into()),
+                format!(
+                    "#{} {}",
+                    id,
+                    utils::programs::bpf_prog_type_to_str(prog.type_)
+                ),
+            ),
+            None => (format!("prog #{}", id), String::new()),
+        }
+    }
+
+    fn map_label(&self, id: u32) -> (String, String) {
+        match self.maps.get(&id) {
+            Some(map) => (
+                utils::programs::cstring_from_i8_array(map.name).unwrap_or_else(|| "N/A".into()),
+                format!("#{} {}", id, utils::maps::bpf_map_type_to_str(map.type_)),
+            ),
+            None => (format!("map #{}", id), String::new()),
+        }
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bpf {\n  rankdir=LR;\n");
+        for &id in self.programs.keys() {
+            let (name, detail) = self.prog_label(id);
+            dot += &format!(
+                "  prog_{id} [shape=box, label=\"{}\\n{}\"];\n",
+                dot_escape(&name),
+                dot_escape(&detail)
+            );
+        }
+        for &id in self.maps.keys() {
+            let (name, detail) = self.map_label(id);
+            dot += &format!(
+                "  map_{id} [shape=cylinder, label=\"{}\\n{}\"];\n",
+                dot_escape(&name),
+                dot_escape(&detail)
+            );
+        }
+        for (prog, map) in &self.edges {
+            dot += &format!("  prog_{prog} -> map_{map};\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart LR\n");
+        for &id in self.programs.keys() {
+            let (name, detail) = self.prog_label(id);
+            mermaid += &format!(
+                "  prog_{id}[\"{}<br/>{}\"]\n",
+                mermaid_escape(&name),
+                mermaid_escape(&detail)
+            );
+        }
+        for &id in self.maps.keys() {
+            let (name, detail) = self.map_label(id);
+            mermaid += &format!(
+                "  map_{id}[(\"{}<br/>{}\")]\n",
+                mermaid_escape(&name),
+                mermaid_escape(&detail)
+            );
+        }
+        for (prog, map) in &self.edges {
+            mermaid += &format!("  prog_{prog} --> map_{map}\n");
+        }
+        mermaid
+    }
+}
+
+fn dot_escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(label: &str) -> String {
+    label.replace('"', "#quot;")
+}
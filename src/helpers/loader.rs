@@ -0,0 +1,331 @@
+use std::{
+    ffi::{CStr, CString, c_char, c_int},
+    io, mem,
+    path::Path,
+    ptr,
+    sync::Mutex,
+};
+
+use libbpf_sys::{
+    __va_list_tag, LIBBPF_DEBUG, bpf_map, bpf_map__is_internal, bpf_map__key_size,
+    bpf_map__max_entries, bpf_map__name, bpf_map__set_pin_path, bpf_map__type, bpf_map__value_size,
+    bpf_object, bpf_object__close, bpf_object__load, bpf_object__next_map,
+    bpf_object__next_program, bpf_object__open_file, bpf_prog_get_info_by_fd, bpf_prog_info,
+    bpf_program, bpf_program__fd, bpf_program__name, bpf_program__pin, bpf_program__section_name,
+    bpf_program__set_autoload, bpf_program__set_log_buf, bpf_program__type, libbpf_print_level,
+    libbpf_set_print,
+};
+use ratatui::widgets::Cell;
+
+use crate::{uis::table::TableRow, utils};
+
+// Verifier logs of large programs run to megabytes; anything past this is cut off.
+const LOG_BUF_SIZE: usize = 4 << 20;
+
+// What libbpf printed since the last `take_libbpf_log`.
+static LIBBPF_LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+unsafe extern "C" {
+    fn vsnprintf(
+        buf: *mut c_char,
+        size: usize,
+        fmt: *const c_char,
+        ap: *mut __va_list_tag,
+    ) -> c_int;
+}
+
+// libbpf prints its warnings to stderr by default, which would scribble over the TUI.
+// They are collected here instead and end up in the load report.
+unsafe extern "C" fn libbpf_print(
+    level: libbpf_print_level,
+    fmt: *const c_char,
+    ap: *mut __va_list_tag,
+) -> c_int {
+    if level == LIBBPF_DEBUG {
+        return 0;
+    }
+    let mut buf = [0u8; 1024];
+    let n = unsafe { vsnprintf(buf.as_mut_ptr() as *mut c_char, buf.len(), fmt, ap) };
+    if n < 0 {
+        return n;
+    }
+    let msg = CStr::from_bytes_until_nul(&buf)
+        .map(|msg| msg.to_string_lossy())
+        .unwrap_or_default();
+    if let Ok(mut log) = LIBBPF_LOG.lock() {
+        log.extend(msg.lines().map(str::to_string));
+    }
+    n
+}
+
+fn take_libbpf_log() -> Vec<String> {
+    LIBBPF_LOG
+        .lock()
+        .map(|mut log| mem::take(&mut *log))
+        .unwrap_or_default()
+}
+
+/// A program in an object file, and what to do with it on load.
+pub struct ObjectProgram {
+    ptr: *mut bpf_program,
+    pub name: String,
+    pub section: String,
+    pub type_: u32,
+    pub load: bool,
+    pub pin: Option<String>,
+    // Filled by libbpf with the verifier log if loading fails.
+    log: Vec<u8>,
+}
+
+impl TableRow for ObjectProgram {
+    fn id(&self) -> u32 {
+        0
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        vec![
+            Cell::from(if self.load { "[x]" } else { "[ ]" }),
+            Cell::from(self.name.clone()),
+            Cell::from(self.section.clone()),
+            Cell::from(utils::programs::bpf_prog_type_to_str(self.type_)),
+            Cell::from(self.pin.clone().unwrap_or_default()),
+        ]
+    }
+}
+
+/// A map in an object file, and where to pin it on load.
+pub struct ObjectMap {
+    ptr: *mut bpf_map,
+    pub name: String,
+    pub type_: u32,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+    /// Backs a global data section (.data, .bss, .rodata, ...) rather than a declared map.
+    pub internal: bool,
+    pub pin: Option<String>,
+}
+
+impl TableRow for ObjectMap {
+    fn id(&self) -> u32 {
+        0
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        vec![
+            Cell::from(self.name.clone()),
+            Cell::from(utils::maps::bpf_map_type_to_str(self.type_)),
+            Cell::from(format!("{}/{}", self.key_size, self.value_size)),
+            Cell::from(self.max_entries.to_string()),
+            Cell::from(self.pin.clone().unwrap_or_default()),
+        ]
+    }
+}
+
+/// The outcome of `BpfObject::load`, one line per program and map, or the error and
+/// verifier logs on failure.
+pub struct LoadReport {
+    pub ok: bool,
+    pub lines: Vec<String>,
+}
+
+/// An ELF object opened with libbpf. Programs loaded from it stay loaded until it is
+/// dropped, unless they were pinned.
+pub struct BpfObject {
+    ptr: *mut bpf_object,
+    pub path: String,
+    pub programs: Vec<ObjectProgram>,
+    pub maps: Vec<ObjectMap>,
+}
+
+impl Drop for BpfObject {
+    fn drop(&mut self) {
+        unsafe { bpf_object__close(self.ptr) };
+    }
+}
+
+impl BpfObject {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        unsafe { libbpf_set_print(Some(libbpf_print)) };
+        take_libbpf_log();
+
+        let c_path = CString::new(path.as_os_str().as_encoded_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let ptr = unsafe { bpf_object__open_file(c_path.as_ptr(), ptr::null()) };
+        if ptr.is_null() {
+            let err = io::Error::last_os_error();
+            let log = take_libbpf_log();
+            if log.is_empty() {
+                return Err(err);
+            }
+            let log: Vec<_> = log
+                .iter()
+                .map(|line| line.trim_start_matches("libbpf: "))
+                .collect();
+            return Err(io::Error::new(err.kind(), log.join("; ")));
+        }
+
+        let mut programs = Vec::new();
+        let mut prog = ptr::null_mut();
+        loop {
+            prog = unsafe { bpf_object__next_program(ptr, prog) };
+            if prog.is_null() {
+                break;
+            }
+            programs.push(ObjectProgram {
+                ptr: prog,
+                name: c_string(unsafe { bpf_program__name(prog) }),
+                section: c_string(unsafe { bpf_program__section_name(prog) }),
+                type_: unsafe { bpf_program__type(prog) },
+                load: true,
+                pin: None,
+                log: Vec::new(),
+            });
+        }
+
+        let mut maps = Vec::new();
+        let mut map = ptr::null_mut();
+        loop {
+            map = unsafe { bpf_object__next_map(ptr, map) };
+            if map.is_null() {
+                break;
+            }
+            maps.push(ObjectMap {
+                ptr: map,
+                name: c_string(unsafe { bpf_map__name(map) }),
+                type_: unsafe { bpf_map__type(map) },
+                key_size: unsafe { bpf_map__key_size(map) },
+                value_size: unsafe { bpf_map__value_size(map) },
+                max_entries: unsafe { bpf_map__max_entries(map) },
+                internal: unsafe { bpf_map__is_internal(map) },
+                pin: None,
+            });
+        }
+
+        Ok(Self {
+            ptr,
+            path: path.display().to_string(),
+            programs,
+            maps,
+        })
+    }
+
+    /// Loads the programs marked `load` along with the maps, then pins what was given a
+    /// pin path. An object can only be loaded once, whether or not that succeeds.
+    pub fn load(&mut self) -> LoadReport {
+        for prog in &mut self.programs {
+            unsafe { bpf_program__set_autoload(prog.ptr, prog.load) };
+            if prog.load {
+                prog.log = vec![0; LOG_BUF_SIZE];
+                unsafe {
+                    bpf_program__set_log_buf(
+                        prog.ptr,
+                        prog.log.as_mut_ptr() as *mut c_char,
+                        prog.log.len() as u64,
+                    )
+                };
+            }
+        }
+        take_libbpf_log();
+        let mut lines = Vec::new();
+        for map in &self.maps {
+            // libbpf pins these itself, reusing a compatible map already pinned there.
+            let Some(pin) = map.pin.as_deref().and_then(|p| CString::new(p).ok()) else {
+                continue;
+            };
+            if unsafe { bpf_map__set_pin_path(map.ptr, pin.as_ptr()) } != 0 {
+                lines.push(format!("invalid pin path for map {}", map.name));
+            }
+        }
+
+        let ret = unsafe { bpf_object__load(self.ptr) };
+        if ret != 0 {
+            lines.push(format!(
+                "loading {} failed: {}",
+                self.path,
+                io::Error::from_raw_os_error(-ret)
+            ));
+            lines.extend(take_libbpf_log());
+            for prog in self.programs.iter().filter(|prog| prog.load) {
+                let log = CStr::from_bytes_until_nul(&prog.log)
+                    .map(|log| log.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                if log.is_empty() {
+                    continue;
+                }
+                lines.push(String::new());
+                lines.push(format!("verifier log for {}:", prog.name));
+                lines.extend(log.lines().map(str::to_string));
+            }
+            return LoadReport { ok: false, lines };
+        }
+
+        let mut ok = true;
+        for prog in self.programs.iter().filter(|prog| prog.load) {
+            let fd = unsafe { bpf_program__fd(prog.ptr) };
+            let mut info: bpf_prog_info = unsafe { mem::zeroed() };
+            let mut len = mem::size_of_val(&info) as u32;
+            match unsafe { bpf_prog_get_info_by_fd(fd, &mut info, &mut len) } {
+                0 => lines.push(format!("loaded {} as program #{}", prog.name, info.id)),
+                err => {
+                    ok = false;
+                    lines.push(format!(
+                        "loaded {}, but reading its info failed: {}",
+                        prog.name,
+                        io::Error::from_raw_os_error(-err)
+                    ));
+                }
+            }
+
+            let Some(pin) = &prog.pin else {
+                continue;
+            };
+            let c_pin = CString::new(pin.as_str()).unwrap_or_default();
+            match unsafe { bpf_program__pin(prog.ptr, c_pin.as_ptr()) } {
+                0 => lines.push(format!("  pinned at {}", pin)),
+                err => {
+                    ok = false;
+                    lines.push(format!(
+                        "  pinning at {} failed: {}",
+                        pin,
+                        io::Error::from_raw_os_error(-err)
+                    ));
+                }
+            }
+        }
+        for map in self.maps.iter().filter(|map| map.pin.is_some()) {
+            lines.push(format!(
+                "map {} pinned at {}",
+                map.name,
+                map.pin.as_deref().unwrap_or_default()
+            ));
+        }
+        let warnings = take_libbpf_log();
+        if !warnings.is_empty() {
+            lines.push(String::new());
+            lines.extend(warnings);
+        }
+        if self
+            .programs
+            .iter()
+            .any(|prog| prog.load && prog.pin.is_none())
+        {
+            lines.push(String::new());
+            lines.push("programs without a pin path unload once the object is closed".to_string());
+        }
+        // Logs are only of interest when loading fails.
+        for prog in &mut self.programs {
+            prog.log = Vec::new();
+        }
+        LoadReport { ok, lines }
+    }
+}
+
+fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
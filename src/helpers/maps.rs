@@ -1,8 +1,26 @@
-use libbpf_sys::{bpf_map_get_fd_by_id, bpf_map_get_info_by_fd, bpf_map_get_next_id, bpf_map_info};
-use ratatui::widgets::Cell;
-use std::mem;
+use libbpf_sys::{
+    bpf_map_delete_elem, bpf_map_get_fd_by_id, bpf_map_get_info_by_fd, bpf_map_get_next_id,
+    bpf_map_get_next_key, bpf_map_info, bpf_map_lookup_elem, bpf_map_update_elem, bpf_obj_get,
+};
+use ratatui::{
+    style::{Color, Style},
+    text::Text,
+    widgets::Cell,
+};
+use std::{
+    collections::HashMap,
+    ffi::{CString, c_void},
+    fs, io, mem,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr,
+};
 
-use crate::{uis::table::TableRow, utils};
+use crate::{
+    helpers::{self, btf::Btf},
+    uis::table::TableRow,
+    utils::{self, btf::BtfValue},
+};
 
 impl TableRow for bpf_map_info {
     fn id(&self) -> u32 {
@@ -23,6 +41,125 @@ impl TableRow for bpf_map_info {
     }
 }
 
+/// A map list row with the programs using the map, flagging shared and orphaned maps.
+pub struct MapRow<'a> {
+    pub info: &'a bpf_map_info,
+    pub users: &'a [u32],
+    pub pinned: bool,
+}
+
+impl TableRow for MapRow<'_> {
+    fn id(&self) -> u32 {
+        self.info.id
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        let mut cells = self.info.cells();
+        let used_by = match self.users {
+            [] if self.pinned => "none (pinned)".to_string(),
+            [] => "-".to_string(),
+            ids => ids
+                .iter()
+                .map(|id| format!("#{}", id))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        cells.push(Cell::from(used_by));
+        cells
+    }
+
+    fn style(&self) -> Style {
+        if self.users.is_empty() && self.pinned {
+            Style::default().fg(Color::Red)
+        } else if self.users.len() > 1 {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        }
+    }
+}
+
+/// The reverse of `get_map_ids_by_prog`: which programs use each map, plus where maps
+/// are pinned under the BPF filesystem.
+#[derive(Default)]
+pub struct MapUsage {
+    users: HashMap<u32, Vec<u32>>,
+    pins: HashMap<u32, Vec<String>>,
+    // Names of the programs in `users`, taken in the same pass.
+    program_names: HashMap<u32, String>,
+}
+
+impl MapUsage {
+    pub fn users(&self, map_id: u32) -> &[u32] {
+        self.users.get(&map_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pins(&self, map_id: u32) -> &[String] {
+        self.pins.get(&map_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn program_name(&self, prog_id: u32) -> Option<&str> {
+        self.program_names.get(&prog_id).map(String::as_str)
+    }
+
+    pub fn row<'a>(&'a self, info: &'a bpf_map_info) -> MapRow<'a> {
+        MapRow {
+            info,
+            users: self.users(info.id),
+            pinned: !self.pins(info.id).is_empty(),
+        }
+    }
+}
+
+const BPF_FS_ROOT: &str = "/sys/fs/bpf";
+
+pub fn fetch_map_usage() -> MapUsage {
+    let mut usage = MapUsage::default();
+    for prog in helpers::programs::fetch_programs() {
+        for map_id in helpers::programs::get_map_ids_by_prog(&prog) {
+            usage.users.entry(map_id).or_default().push(prog.id);
+        }
+        if let Some(name) = utils::programs::cstring_from_i8_array(prog.name) {
+            usage.program_names.insert(prog.id, name);
+        }
+    }
+    collect_pins(Path::new(BPF_FS_ROOT), &mut usage.pins);
+    usage
+}
+
+fn collect_pins(dir: &Path, pins: &mut HashMap<u32, Vec<String>>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => collect_pins(&path, pins),
+            Ok(t) if t.is_file() => {
+                let Ok(cpath) = CString::new(path.as_os_str().as_bytes()) else {
+                    continue;
+                };
+                let fd = unsafe { bpf_obj_get(cpath.as_ptr()) };
+                if fd < 0 {
+                    continue;
+                }
+                // Programs and links are pinned here too; the fd's inode tells maps apart.
+                let is_map = fs::read_link(format!("/proc/self/fd/{}", fd))
+                    .is_ok_and(|target| target.as_os_str() == "anon_inode:bpf-map");
+                let mut info: bpf_map_info = unsafe { mem::zeroed() };
+                let mut len = mem::size_of_val(&info) as u32;
+                if is_map && unsafe { bpf_map_get_info_by_fd(fd, &mut info, &mut len) } == 0 {
+                    pins.entry(info.id)
+                        .or_default()
+                        .push(path.display().to_string());
+                }
+                unsafe { libc::close(fd) };
+            }
+            _ => {}
+        }
+    }
+}
+
 pub fn fetch_maps() -> Vec<bpf_map_info> {
     let mut maprams: Vec<bpf_map_info> = Vec::new();
     let mut id: u32 = 0;
@@ -48,3 +185,236 @@ pub fn fetch_maps() -> Vec<bpf_map_info> {
     }
     maprams
 }
+
+pub fn fetch_maps_by_id(id: u32) -> bpf_map_info {
+    let fd = unsafe { bpf_map_get_fd_by_id(id) };
+
+    let mut info: bpf_map_info = unsafe { mem::zeroed() };
+    let mut len = mem::size_of_val(&info) as u32;
+
+    unsafe { bpf_map_get_info_by_fd(fd, &mut info, &mut len) };
+    unsafe { libc::close(fd) };
+    info
+}
+
+pub struct MapEntry {
+    pub key: Vec<u8>,
+    /// One value for regular maps, one per possible CPU for per-CPU maps.
+    pub values: Vec<Vec<u8>>,
+}
+
+pub struct MapEntryRow {
+    pub columns: Vec<Vec<String>>,
+}
+
+impl TableRow for MapEntryRow {
+    fn id(&self) -> u32 {
+        0
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        self.columns
+            .iter()
+            .map(|lines| Cell::from(Text::from_iter(lines.clone())))
+            .collect()
+    }
+
+    fn height(&self) -> u16 {
+        self.columns.iter().map(Vec::len).max().unwrap_or(1).max(1) as u16
+    }
+}
+
+/// Interprets a value as an integer, either through BTF or by its width.
+pub fn numeric_value(btf: Option<&Btf>, type_id: u32, bytes: &[u8]) -> Option<i128> {
+    match btf {
+        Some(btf) if type_id != 0 => match btf.decode(type_id, bytes) {
+            BtfValue::Signed(v) => Some(v),
+            BtfValue::Unsigned(v) => i128::try_from(v).ok(),
+            _ => None,
+        },
+        _ => match bytes.len() {
+            1 | 2 | 4 | 8 => {
+                let mut buf = [0u8; 8];
+                buf[..bytes.len()].copy_from_slice(bytes);
+                Some(u64::from_le_bytes(buf) as i128)
+            }
+            _ => None,
+        },
+    }
+}
+
+pub fn num_possible_cpus() -> usize {
+    fs::read_to_string("/sys/devices/system/cpu/possible")
+        .ok()
+        .and_then(|list| utils::maps::parse_cpu_list(&list).into_iter().max())
+        .map_or(1, |max| max as usize + 1)
+}
+
+/// Renders raw map bytes as a BTF tree when a type is known, or as a hex dump otherwise.
+pub fn format_map_bytes(btf: Option<&Btf>, type_id: u32, bytes: &[u8]) -> Vec<String> {
+    match btf {
+        Some(btf) if type_id != 0 => utils::btf::format_tree(&btf.decode(type_id, bytes)),
+        _ => utils::maps::hex_dump(bytes),
+    }
+}
+
+/// BTF describing a map's data: the map's own, or else that of a program using the map.
+/// Ring buffers and perf event arrays carry no value type, so their record layouts are
+/// only found in the BTF of the programs writing to them.
+pub fn fetch_map_btf(info: &bpf_map_info) -> Option<Btf> {
+    Btf::load_by_id(info.btf_id).or_else(|| {
+        helpers::programs::fetch_programs()
+            .iter()
+            .filter(|prog| helpers::programs::get_map_ids_by_prog(prog).contains(&info.id))
+            .find_map(|prog| Btf::load_by_id(prog.btf_id))
+    })
+}
+
+pub fn fetch_map_entries(info: &bpf_map_info) -> Vec<MapEntry> {
+    let mut entries: Vec<MapEntry> = Vec::new();
+    let fd = unsafe { bpf_map_get_fd_by_id(info.id) };
+    if fd < 0 {
+        return entries;
+    }
+
+    let (stride, ncpus) = if utils::maps::is_percpu_map(info.type_) {
+        (
+            utils::maps::percpu_value_stride(info.value_size),
+            num_possible_cpus(),
+        )
+    } else {
+        ((info.value_size as usize).max(1), 1)
+    };
+
+    let mut prev: Option<Vec<u8>> = None;
+    // Entries can be deleted under us, which restarts hash iteration; cap the walk.
+    for _ in 0..info.max_entries {
+        let mut key = vec![0u8; info.key_size as usize];
+        let prev_ptr = prev
+            .as_ref()
+            .map_or(ptr::null(), |k| k.as_ptr() as *const c_void);
+        if unsafe { bpf_map_get_next_key(fd, prev_ptr, key.as_mut_ptr() as *mut c_void) } != 0 {
+            break;
+        }
+
+        let mut value = vec![0u8; stride * ncpus];
+        if unsafe {
+            bpf_map_lookup_elem(
+                fd,
+                key.as_ptr() as *const c_void,
+                value.as_mut_ptr() as *mut c_void,
+            )
+        } == 0
+        {
+            entries.push(MapEntry {
+                key: key.clone(),
+                values: value
+                    .chunks(stride)
+                    .map(|chunk| chunk[..info.value_size as usize].to_vec())
+                    .collect(),
+            });
+        }
+        prev = Some(key);
+    }
+
+    unsafe { libc::close(fd) };
+    entries
+}
+
+/// Parses a key or value typed by the user: `hex <bytes>`, an integer, or a BTF literal.
+pub fn encode_map_input(
+    btf: Option<&Btf>,
+    type_id: u32,
+    text: &str,
+    size: u32,
+) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let bytes = if text
+        .strip_prefix("hex")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    {
+        utils::maps::parse_hex_bytes(text)?
+    } else {
+        match btf {
+            Some(btf) if type_id != 0 => btf.encode(type_id, &utils::btf::parse_literal(text)?)?,
+            _ => utils::maps::parse_int_bytes(text, size as usize)?,
+        }
+    };
+    if bytes.len() != size as usize {
+        return Err(format!("expected {} bytes, got {}", size, bytes.len()));
+    }
+    Ok(bytes)
+}
+
+/// Writes `values` under `key`. Per-CPU maps take one value per possible CPU, or a
+/// single value that is copied to every CPU.
+pub fn update_map_entry(
+    info: &bpf_map_info,
+    key: &[u8],
+    values: &[Vec<u8>],
+    flags: u64,
+) -> io::Result<()> {
+    let buf = if utils::maps::is_percpu_map(info.type_) {
+        let stride = utils::maps::percpu_value_stride(info.value_size);
+        let ncpus = num_possible_cpus();
+        if values.len() != 1 && values.len() != ncpus {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected 1 or {} per-CPU values, got {}",
+                    ncpus,
+                    values.len()
+                ),
+            ));
+        }
+        let mut buf = vec![0u8; stride * ncpus];
+        for (cpu, chunk) in buf.chunks_mut(stride).enumerate() {
+            let value = &values[cpu % values.len()];
+            chunk[..value.len()].copy_from_slice(value);
+        }
+        buf
+    } else {
+        values.concat()
+    };
+
+    with_map_fd(info.id, |fd| unsafe {
+        bpf_map_update_elem(
+            fd,
+            key.as_ptr() as *const c_void,
+            buf.as_ptr() as *const c_void,
+            flags,
+        )
+    })
+}
+
+/// Looks up a single value; for LPM tries the kernel returns the longest matching prefix.
+pub fn lookup_map_entry(info: &bpf_map_info, key: &[u8]) -> io::Result<Vec<u8>> {
+    let mut value = vec![0u8; info.value_size as usize];
+    with_map_fd(info.id, |fd| unsafe {
+        bpf_map_lookup_elem(
+            fd,
+            key.as_ptr() as *const c_void,
+            value.as_mut_ptr() as *mut c_void,
+        )
+    })?;
+    Ok(value)
+}
+
+pub fn delete_map_entry(info: &bpf_map_info, key: &[u8]) -> io::Result<()> {
+    with_map_fd(info.id, |fd| unsafe {
+        bpf_map_delete_elem(fd, key.as_ptr() as *const c_void)
+    })
+}
+
+fn with_map_fd(id: u32, op: impl FnOnce(i32) -> i32) -> io::Result<()> {
+    let fd = unsafe { bpf_map_get_fd_by_id(id) };
+    if fd < 0 {
+        return Err(io::Error::from_raw_os_error(-fd));
+    }
+    let ret = op(fd);
+    unsafe { libc::close(fd) };
+    if ret < 0 {
+        return Err(io::Error::from_raw_os_error(-ret));
+    }
+    Ok(())
+}
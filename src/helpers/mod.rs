@@ -1,3 +1,10 @@
 pub mod app;
+pub mod btf;
+pub mod disasm;
+pub mod dump;
+pub mod graph;
+pub mod loader;
 pub mod maps;
+pub mod perfbuf;
 pub mod programs;
+pub mod ringbuf;
@@ -0,0 +1,89 @@
+use std::{ffi::c_void, io, mem, ptr};
+
+use libbpf_sys::{
+    bpf_map_get_fd_by_id, bpf_map_info, perf_buffer, perf_buffer__consume, perf_buffer__free,
+    perf_buffer__new,
+};
+
+// Pages of ring per CPU; libbpf requires a power of two.
+const PAGE_CNT: u64 = 8;
+
+pub enum PerfRecord {
+    Sample { cpu: u32, data: Vec<u8> },
+    Lost { cpu: u32, count: u64 },
+}
+
+/// Per-CPU perf buffers attached to the slots of a BPF_MAP_TYPE_PERF_EVENT_ARRAY map.
+///
+/// Attaching replaces whatever perf events the slots held before, and dropping the buffer
+/// deletes every slot. The change is permanent: the program that owns the map does not get
+/// its samples back once this buffer is closed, unless it fills the slots in again itself.
+pub struct PerfBuffer {
+    fd: i32,
+    pb: *mut perf_buffer,
+    records: *mut Vec<PerfRecord>,
+}
+
+impl Drop for PerfBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            perf_buffer__free(self.pb);
+            libc::close(self.fd);
+            drop(Box::from_raw(self.records));
+        }
+    }
+}
+
+unsafe extern "C" fn on_sample(ctx: *mut c_void, cpu: i32, data: *mut c_void, size: u32) {
+    let records = unsafe { &mut *(ctx as *mut Vec<PerfRecord>) };
+    let data = unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) };
+    records.push(PerfRecord::Sample {
+        cpu: cpu as u32,
+        data: data.to_vec(),
+    });
+}
+
+unsafe extern "C" fn on_lost(ctx: *mut c_void, cpu: i32, count: u64) {
+    let records = unsafe { &mut *(ctx as *mut Vec<PerfRecord>) };
+    records.push(PerfRecord::Lost {
+        cpu: cpu as u32,
+        count,
+    });
+}
+
+impl PerfBuffer {
+    pub fn open(info: &bpf_map_info) -> io::Result<Self> {
+        let fd = unsafe { bpf_map_get_fd_by_id(info.id) };
+        if fd < 0 {
+            return Err(io::Error::from_raw_os_error(-fd));
+        }
+        let records = Box::into_raw(Box::new(Vec::new()));
+        let pb = unsafe {
+            perf_buffer__new(
+                fd,
+                PAGE_CNT,
+                Some(on_sample),
+                Some(on_lost),
+                records as *mut c_void,
+                ptr::null(),
+            )
+        };
+        if pb.is_null() {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+                drop(Box::from_raw(records));
+            }
+            return Err(err);
+        }
+        Ok(Self { fd, pb, records })
+    }
+
+    /// Reads every pending sample and lost-sample notification without blocking.
+    pub fn consume(&mut self) -> Vec<PerfRecord> {
+        unsafe {
+            perf_buffer__consume(self.pb);
+            mem::take(&mut *self.records)
+        }
+    }
+}
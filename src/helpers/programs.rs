@@ -1,13 +1,23 @@
-use std::mem;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io, mem,
+    time::{Duration, Instant},
+};
 
 use libbpf_sys::{
-    bpf_prog_get_fd_by_id, bpf_prog_get_info_by_fd, bpf_prog_get_next_id, bpf_prog_info,
+    BPF_STATS_RUN_TIME, bpf_enable_stats, bpf_func_info, bpf_line_info, bpf_prog_get_fd_by_id,
+    bpf_prog_get_info_by_fd, bpf_prog_get_next_id, bpf_prog_info,
 };
 extern crate libc;
 
-use crate::{uis::table::TableRow, utils};
+use crate::{helpers::btf::Btf, uis::table::TableRow, utils};
 use ratatui::widgets::Cell;
 
+const BPF_STATS_SYSCTL: &str = "/proc/sys/kernel/bpf_stats_enabled";
+const HISTORY_STEP: Duration = Duration::from_secs(1);
+/// How far back `RunHistory` keeps samples, which is the longest chart window.
+pub const HISTORY_SPAN: Duration = Duration::from_secs(60 * 60);
+
 impl TableRow for bpf_prog_info {
     fn id(&self) -> u32 {
         self.id
@@ -89,3 +99,411 @@ pub fn get_map_ids_by_prog(info: &bpf_prog_info) -> Vec<u32> {
     map_ids.truncate(full_info.nr_map_ids as usize);
     map_ids
 }
+
+/// Repeats `bpf_prog_get_info_by_fd` with one of the info's array fields pointed at `buf`
+/// and returns the part the kernel filled in. `request` sets the field's pointer and
+/// capacity; `filled` reads back its pointer and length, the pointer being cleared when the
+/// caller may not see raw kernel data.
+fn get_info_array<T>(
+    id: u32,
+    mut buf: Vec<T>,
+    request: impl FnOnce(&mut bpf_prog_info, u64),
+    filled: impl FnOnce(&bpf_prog_info) -> (u64, u32),
+) -> Vec<T> {
+    let fd = unsafe { bpf_prog_get_fd_by_id(id) };
+    if fd < 0 {
+        return Vec::new();
+    }
+
+    let mut full_info: bpf_prog_info = unsafe { mem::zeroed() };
+    request(&mut full_info, buf.as_mut_ptr() as u64);
+
+    let mut len = mem::size_of_val(&full_info) as u32;
+    let ret = unsafe { bpf_prog_get_info_by_fd(fd, &mut full_info, &mut len) };
+    unsafe { libc::close(fd) };
+
+    match filled(&full_info) {
+        (ptr, n) if ret == 0 && ptr != 0 => {
+            buf.truncate(n as usize);
+            buf
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Fetches the program's instructions as rewritten by the verifier. The kernel only hands
+/// them out to privileged callers, so this is empty otherwise.
+pub fn get_xlated_insns(info: &bpf_prog_info) -> Vec<u8> {
+    get_info_array(
+        info.id,
+        vec![0u8; info.xlated_prog_len as usize],
+        |full, ptr| {
+            full.xlated_prog_len = info.xlated_prog_len;
+            full.xlated_prog_insns = ptr;
+        },
+        |full| (full.xlated_prog_insns, full.xlated_prog_len),
+    )
+}
+
+/// Fetches the native code the JIT compiled the program into, all functions back to back.
+pub fn get_jited_insns(info: &bpf_prog_info) -> Vec<u8> {
+    get_info_array(
+        info.id,
+        vec![0u8; info.jited_prog_len as usize],
+        |full, ptr| {
+            full.jited_prog_len = info.jited_prog_len;
+            full.jited_prog_insns = ptr;
+        },
+        |full| (full.jited_prog_insns, full.jited_prog_len),
+    )
+}
+
+/// Fetches the kernel addresses of the program's JITed functions, main program first.
+pub fn get_jited_ksyms(info: &bpf_prog_info) -> Vec<u64> {
+    get_info_array(
+        info.id,
+        vec![0u64; info.nr_jited_ksyms as usize],
+        |full, ptr| {
+            full.nr_jited_ksyms = info.nr_jited_ksyms;
+            full.jited_ksyms = ptr;
+        },
+        |full| (full.jited_ksyms, full.nr_jited_ksyms),
+    )
+}
+
+/// Fetches the byte length of each JITed function, in the same order as the ksyms.
+pub fn get_jited_func_lens(info: &bpf_prog_info) -> Vec<u32> {
+    get_info_array(
+        info.id,
+        vec![0u32; info.nr_jited_func_lens as usize],
+        |full, ptr| {
+            full.nr_jited_func_lens = info.nr_jited_func_lens;
+            full.jited_func_lens = ptr;
+        },
+        |full| (full.jited_func_lens, full.nr_jited_func_lens),
+    )
+}
+
+/// Fetches the records mapping xlated instruction offsets to source lines in the program's BTF.
+pub fn get_line_info(info: &bpf_prog_info) -> Vec<bpf_line_info> {
+    get_info_array(
+        info.id,
+        vec![unsafe { mem::zeroed::<bpf_line_info>() }; info.nr_line_info as usize],
+        |full, ptr| {
+            full.nr_line_info = info.nr_line_info;
+            full.line_info_rec_size = mem::size_of::<bpf_line_info>() as u32;
+            full.line_info = ptr;
+        },
+        |full| (full.line_info, full.nr_line_info),
+    )
+}
+
+/// Fetches the native address each line_info record starts at, in line_info order.
+pub fn get_jited_line_info(info: &bpf_prog_info) -> Vec<u64> {
+    get_info_array(
+        info.id,
+        vec![0u64; info.nr_jited_line_info as usize],
+        |full, ptr| {
+            full.nr_jited_line_info = info.nr_jited_line_info;
+            full.jited_line_info_rec_size = mem::size_of::<u64>() as u32;
+            full.jited_line_info = ptr;
+        },
+        |full| (full.jited_line_info, full.nr_jited_line_info),
+    )
+}
+
+/// Fetches the records naming the BTF function each subprogram starts with.
+pub fn get_func_info(info: &bpf_prog_info) -> Vec<bpf_func_info> {
+    get_info_array(
+        info.id,
+        vec![unsafe { mem::zeroed::<bpf_func_info>() }; info.nr_func_info as usize],
+        |full, ptr| {
+            full.nr_func_info = info.nr_func_info;
+            full.func_info_rec_size = mem::size_of::<bpf_func_info>() as u32;
+            full.func_info = ptr;
+        },
+        |full| (full.func_info, full.nr_func_info),
+    )
+}
+
+/// A function of a program, as described by its func_info and BTF.
+pub struct Subprog {
+    pub name: String,
+    pub prototype: String,
+    pub insn_off: u32,
+    /// Size of the function's native code, when the program is JITed.
+    pub jited_len: Option<u32>,
+}
+
+impl TableRow for Subprog {
+    fn id(&self) -> u32 {
+        self.insn_off
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        vec![
+            Cell::from(self.name.clone()),
+            Cell::from(self.prototype.clone()),
+            Cell::from(self.insn_off.to_string()),
+            Cell::from(
+                self.jited_len
+                    .map_or_else(|| "-".into(), |len| format!("{}B", len)),
+            ),
+        ]
+    }
+}
+
+/// Lists the program's subprograms, main program first. Empty for programs loaded without BTF.
+pub fn fetch_subprogs(info: &bpf_prog_info) -> Vec<Subprog> {
+    let Some(btf) = Btf::load_by_id(info.btf_id) else {
+        return Vec::new();
+    };
+    let jited_lens = get_jited_func_lens(info);
+    get_func_info(info)
+        .iter()
+        .enumerate()
+        .map(|(i, func)| Subprog {
+            name: btf.func_name(func.type_id).unwrap_or_else(|| "N/A".into()),
+            prototype: btf.func_prototype(func.type_id).unwrap_or_default(),
+            insn_off: func.insn_off,
+            jited_len: jited_lens.get(i).copied(),
+        })
+        .collect()
+}
+
+/// Whether the sysctl has stats on. Stats enabled through a BPF_ENABLE_STATS fd do not
+/// show up here.
+pub fn stats_enabled() -> bool {
+    fs::read_to_string(BPF_STATS_SYSCTL).is_ok_and(|v| v.trim() != "0")
+}
+
+/// Runtime stats collection switched on by eman, switched back off when dropped. Other
+/// users of stats keep them on regardless.
+pub enum StatsHandle {
+    /// A BPF_ENABLE_STATS fd; the kernel counts for as long as any of these is open.
+    Fd(i32),
+    /// Kernels without BPF_ENABLE_STATS (before 5.8) only have the sysctl. `flipped` is set
+    /// when eman turned it on, and only then turns it back off.
+    Sysctl { flipped: bool },
+}
+
+impl StatsHandle {
+    pub fn enable() -> io::Result<Self> {
+        let fd = unsafe { bpf_enable_stats(BPF_STATS_RUN_TIME) };
+        if fd >= 0 {
+            return Ok(StatsHandle::Fd(fd));
+        }
+        let flipped = !stats_enabled();
+        if flipped {
+            fs::write(BPF_STATS_SYSCTL, "1")?;
+        }
+        Ok(StatsHandle::Sysctl { flipped })
+    }
+}
+
+impl Drop for StatsHandle {
+    fn drop(&mut self) {
+        match *self {
+            StatsHandle::Fd(fd) => unsafe {
+                libc::close(fd);
+            },
+            StatsHandle::Sysctl { flipped: true } => {
+                let _ = fs::write(BPF_STATS_SYSCTL, "0");
+            }
+            StatsHandle::Sysctl { flipped: false } => {}
+        }
+    }
+}
+
+pub fn online_cpus() -> u32 {
+    unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }.max(1) as u32
+}
+
+/// How busy a program kept the CPUs between two samples.
+pub struct ProgramLoad {
+    pub info: bpf_prog_info,
+    pub runs_per_sec: f64,
+    pub ns_per_run: f64,
+    /// Share of all online CPUs' time spent running the program.
+    pub cpu_percent: f64,
+}
+
+impl TableRow for ProgramLoad {
+    fn id(&self) -> u32 {
+        self.info.id
+    }
+
+    fn cells(&self) -> Vec<Cell<'static>> {
+        let name = utils::programs::cstring_from_i8_array(self.info.name)
+            .unwrap_or_else(|| "N/A".to_string());
+        vec![
+            Cell::from(self.info.id.to_string()),
+            Cell::from(name),
+            Cell::from(utils::programs::bpf_prog_type_to_str(self.info.type_)),
+            Cell::from(format!("{:.1}", self.runs_per_sec)),
+            Cell::from(format!("{:.0}", self.ns_per_run)),
+            Cell::from(format!("{:.2}%", self.cpu_percent)),
+        ]
+    }
+}
+
+/// Computes each program's load from the growth of its run counters since `previous`,
+/// taken `elapsed` ago. Programs loaded in between count from zero.
+pub fn program_load(
+    previous: &HashMap<u32, bpf_prog_info>,
+    current: &[bpf_prog_info],
+    elapsed: Duration,
+    cpus: u32,
+) -> Vec<ProgramLoad> {
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    current
+        .iter()
+        .map(|info| {
+            let (runs, ns) = match previous.get(&info.id) {
+                Some(prev) => (
+                    info.run_cnt.saturating_sub(prev.run_cnt),
+                    info.run_time_ns.saturating_sub(prev.run_time_ns),
+                ),
+                None => (info.run_cnt, info.run_time_ns),
+            };
+            ProgramLoad {
+                info: *info,
+                runs_per_sec: runs as f64 / secs,
+                ns_per_run: if runs == 0 {
+                    0.0
+                } else {
+                    ns as f64 / runs as f64
+                },
+                cpu_percent: ns as f64 / (secs * 1e9 * cpus as f64) * 100.0,
+            }
+        })
+        .collect()
+}
+
+struct RunSample {
+    at: Instant,
+    run_cnt: u64,
+    run_time_ns: u64,
+    recursion_misses: u64,
+}
+
+/// Per-second rates derived from consecutive samples, as `(seconds ago, value)` points
+/// with the x values negative so that charts end at "now".
+#[derive(Default)]
+pub struct RunSeries {
+    pub runs_per_sec: Vec<(f64, f64)>,
+    pub ns_per_run: Vec<(f64, f64)>,
+    pub misses_per_sec: Vec<(f64, f64)>,
+}
+
+/// Rolling history of each program's run counters, at most one sample a second.
+#[derive(Default)]
+pub struct RunHistory {
+    samples: HashMap<u32, VecDeque<RunSample>>,
+    last: Option<Instant>,
+}
+
+impl RunHistory {
+    pub fn record(&mut self, programs: &[bpf_prog_info]) {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now - last < HISTORY_STEP) {
+            return;
+        }
+        self.last = Some(now);
+
+        // Programs that have been unloaded take their history with them.
+        self.samples
+            .retain(|id, _| programs.iter().any(|prog| prog.id == *id));
+        for prog in programs {
+            let samples = self.samples.entry(prog.id).or_default();
+            samples.push_back(RunSample {
+                at: now,
+                run_cnt: prog.run_cnt,
+                run_time_ns: prog.run_time_ns,
+                recursion_misses: prog.recursion_misses,
+            });
+            while samples
+                .front()
+                .is_some_and(|sample| now - sample.at > HISTORY_SPAN)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    pub fn series(&self, id: u32, window: Duration) -> RunSeries {
+        let mut series = RunSeries::default();
+        let Some(samples) = self.samples.get(&id) else {
+            return series;
+        };
+        let now = Instant::now();
+        for (prev, cur) in samples.iter().zip(samples.iter().skip(1)) {
+            let ago = now - cur.at;
+            if ago > window {
+                continue;
+            }
+            let x = -ago.as_secs_f64();
+            let secs = (cur.at - prev.at).as_secs_f64().max(f64::EPSILON);
+            let runs = cur.run_cnt.saturating_sub(prev.run_cnt);
+            let ns = cur.run_time_ns.saturating_sub(prev.run_time_ns);
+            let misses = cur.recursion_misses.saturating_sub(prev.recursion_misses);
+            series.runs_per_sec.push((x, runs as f64 / secs));
+            series.ns_per_run.push((
+                x,
+                if runs == 0 {
+                    0.0
+                } else {
+                    ns as f64 / runs as f64
+                },
+            ));
+            series.misses_per_sec.push((x, misses as f64 / secs));
+        }
+        series
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prog(id: u32, run_cnt: u64, run_time_ns: u64) -> bpf_prog_info {
+        bpf_prog_info {
+            id,
+            run_cnt,
+            run_time_ns,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn load_from_counter_deltas() {
+        let previous = HashMap::from([(1, prog(1, 100, 10_000)), (2, prog(2, 5, 500))]);
+        let current = [prog(1, 300, 410_000), prog(2, 5, 500), prog(3, 10, 2_000)];
+        let load = program_load(&previous, &current, Duration::from_secs(2), 2);
+
+        // 200 runs taking 400us over 2s on 2 CPUs.
+        assert_eq!(load[0].info.id, 1);
+        assert_eq!(load[0].runs_per_sec, 100.0);
+        assert_eq!(load[0].ns_per_run, 2_000.0);
+        assert_eq!(load[0].cpu_percent, 400_000.0 / 4e9 * 100.0);
+
+        // No runs in between: zero, not NaN.
+        assert_eq!(load[1].runs_per_sec, 0.0);
+        assert_eq!(load[1].ns_per_run, 0.0);
+        assert_eq!(load[1].cpu_percent, 0.0);
+
+        // Loaded after the previous sample, so everything it ran counts.
+        assert_eq!(load[2].runs_per_sec, 5.0);
+        assert_eq!(load[2].ns_per_run, 200.0);
+    }
+
+    #[test]
+    fn load_never_goes_negative() {
+        // A sample below the previous one must not wrap around into a huge delta.
+        let previous = HashMap::from([(1, prog(1, 100, 10_000))]);
+        let load = program_load(&previous, &[prog(1, 10, 1_000)], Duration::ZERO, 1);
+        assert_eq!(load[0].runs_per_sec, 0.0);
+        assert_eq!(load[0].ns_per_run, 0.0);
+        assert_eq!(load[0].cpu_percent, 0.0);
+    }
+}
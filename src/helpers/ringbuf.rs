@@ -0,0 +1,141 @@
+use std::{
+    io, ptr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use libbpf_sys::{bpf_map_get_fd_by_id, bpf_map_info};
+
+// Record header flags, see BPF_RINGBUF_BUSY_BIT / BPF_RINGBUF_DISCARD_BIT in the UAPI.
+const BUSY_BIT: u32 = 1 << 31;
+const DISCARD_BIT: u32 = 1 << 30;
+const HDR_SZ: u64 = 8;
+
+/// A user-space consumer of a BPF_MAP_TYPE_RINGBUF map, reading the ring through mmap.
+///
+/// There is a single consumer position per map, so records consumed here are gone for
+/// whichever process normally reads the ring.
+pub struct RingBuffer {
+    fd: i32,
+    page_size: usize,
+    size: usize,
+    consumer: *mut libc::c_void,
+    producer: *mut libc::c_void,
+}
+
+impl Drop for RingBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.consumer, self.page_size);
+            libc::munmap(self.producer, self.page_size + 2 * self.size);
+            libc::close(self.fd);
+        }
+    }
+}
+
+impl RingBuffer {
+    pub fn open(info: &bpf_map_info) -> io::Result<Self> {
+        let fd = unsafe { bpf_map_get_fd_by_id(info.id) };
+        if fd < 0 {
+            return Err(io::Error::from_raw_os_error(-fd));
+        }
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let size = info.max_entries as usize;
+
+        // The consumer position page is writable; the producer page and the data area are
+        // read-only, with the data mapped twice so records can wrap around contiguously.
+        let consumer = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                page_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if consumer == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(err);
+        }
+        let producer = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                page_size + 2 * size,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                fd,
+                page_size as libc::off_t,
+            )
+        };
+        if producer == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::munmap(consumer, page_size);
+                libc::close(fd);
+            }
+            return Err(err);
+        }
+
+        Ok(Self {
+            fd,
+            page_size,
+            size,
+            consumer,
+            producer,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn consumer_pos(&self) -> &AtomicU64 {
+        unsafe { &*(self.consumer as *const AtomicU64) }
+    }
+
+    fn producer_pos(&self) -> &AtomicU64 {
+        unsafe { &*(self.producer as *const AtomicU64) }
+    }
+
+    fn data(&self) -> *const u8 {
+        unsafe { (self.producer as *const u8).add(self.page_size) }
+    }
+
+    /// Bytes committed by producers that have not been consumed yet.
+    pub fn pending(&self) -> u64 {
+        let producer = self.producer_pos().load(Ordering::Acquire);
+        let consumer = self.consumer_pos().load(Ordering::Acquire);
+        producer.saturating_sub(consumer)
+    }
+
+    /// Hands every committed record to `on_record`, returning how many were consumed.
+    pub fn consume(&mut self, mut on_record: impl FnMut(&[u8])) -> usize {
+        let mask = self.size as u64 - 1;
+        let mut consumer = self.consumer_pos().load(Ordering::Acquire);
+        let mut count = 0;
+        loop {
+            let producer = self.producer_pos().load(Ordering::Acquire);
+            if consumer >= producer {
+                break;
+            }
+            let header = unsafe { self.data().add((consumer & mask) as usize) };
+            let len = unsafe { &*(header as *const AtomicU32) }.load(Ordering::Acquire);
+            if len & BUSY_BIT != 0 {
+                break;
+            }
+
+            let data_len = (len & !(BUSY_BIT | DISCARD_BIT)) as u64;
+            if len & DISCARD_BIT == 0 {
+                let sample = unsafe {
+                    std::slice::from_raw_parts(header.add(HDR_SZ as usize), data_len as usize)
+                };
+                on_record(sample);
+                count += 1;
+            }
+            consumer += (data_len + HDR_SZ).next_multiple_of(8);
+            self.consumer_pos().store(consumer, Ordering::Release);
+        }
+        count
+    }
+}
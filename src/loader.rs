@@ -0,0 +1,365 @@
+use std::{path::Path, time::Duration};
+
+use color_eyre::Result;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Margin},
+    widgets::{Block, BorderType, Clear, Paragraph, TableState},
+};
+
+use crate::{
+    helpers::loader::{BpfObject, LoadReport},
+    uis::{self, input::TextInput},
+};
+
+const DEFAULT_OBJECT: &str = "test/prog/simple_prog.o";
+const HEADER_TEXT: [&str; 1] = ["Load eBPF object"];
+
+pub struct Loader {
+    screen: Screen,
+    dialog: Option<Dialog>,
+    leaving: bool,
+}
+
+enum Screen {
+    Open(OpenForm),
+    Object(Box<ObjectView>),
+}
+
+struct OpenForm {
+    path: TextInput,
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Programs,
+    Maps,
+}
+
+struct ObjectView {
+    object: BpfObject,
+    prog_state: TableState,
+    map_state: TableState,
+    focus: Pane,
+    // Set once `object` has been loaded; libbpf cannot load an object twice.
+    loaded: bool,
+}
+
+enum Dialog {
+    Pin(TextInput),
+    Report(LoadReport, u16),
+}
+
+impl ObjectView {
+    fn new(object: BpfObject) -> Self {
+        Self {
+            object,
+            prog_state: TableState::default().with_selected(0),
+            map_state: TableState::default().with_selected(0),
+            focus: Pane::Programs,
+            loaded: false,
+        }
+    }
+
+    fn focused(&mut self) -> (&mut TableState, usize) {
+        match self.focus {
+            Pane::Programs => (&mut self.prog_state, self.object.programs.len()),
+            Pane::Maps => (&mut self.map_state, self.object.maps.len()),
+        }
+    }
+
+    fn next_row(&mut self) {
+        let (state, len) = self.focused();
+        if let Some(i) = state.selected() {
+            state.select(Some((i + 1).min(len.saturating_sub(1))));
+        }
+    }
+
+    fn previous_row(&mut self) {
+        let (state, _) = self.focused();
+        if let Some(i) = state.selected() {
+            state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    fn switch_focus(&mut self) {
+        self.focus = match self.focus {
+            Pane::Programs => Pane::Maps,
+            Pane::Maps => Pane::Programs,
+        };
+    }
+
+    fn toggle_load(&mut self) {
+        let selected = self.prog_state.selected();
+        if let Some(prog) = selected.and_then(|i| self.object.programs.get_mut(i)) {
+            prog.load = !prog.load;
+        }
+    }
+
+    /// Pin path slot of the selected program or map, whichever table has focus.
+    fn selected_pin(&mut self) -> Option<&mut Option<String>> {
+        match self.focus {
+            Pane::Programs => {
+                let i = self.prog_state.selected()?;
+                self.object.programs.get_mut(i).map(|prog| &mut prog.pin)
+            }
+            Pane::Maps => {
+                let i = self.map_state.selected()?;
+                self.object.maps.get_mut(i).map(|map| &mut map.pin)
+            }
+        }
+    }
+
+    /// Opens the file afresh after a failed load, keeping the user's choices, so that
+    /// they can be adjusted and loading retried.
+    fn reopen(&mut self) -> std::io::Result<()> {
+        let mut object = BpfObject::open(Path::new(&self.object.path))?;
+        for prog in &mut object.programs {
+            if let Some(old) = self.object.programs.iter().find(|p| p.name == prog.name) {
+                prog.load = old.load;
+                prog.pin = old.pin.clone();
+            }
+        }
+        for map in &mut object.maps {
+            if let Some(old) = self.object.maps.iter().find(|m| m.name == map.name) {
+                map.pin = old.pin.clone();
+            }
+        }
+        self.object = object;
+        self.loaded = false;
+        Ok(())
+    }
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self {
+            screen: Screen::Open(OpenForm {
+                path: TextInput::new(DEFAULT_OBJECT.to_string()),
+                error: None,
+            }),
+            dialog: None,
+            leaving: false,
+        }
+    }
+
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.leaving {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if event::poll(Duration::from_millis(100))?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                if self.dialog.is_some() {
+                    self.handle_dialog_key(key.code);
+                } else {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        match self.screen {
+            Screen::Open(ref mut form) => match code {
+                KeyCode::Esc => self.leaving = true,
+                KeyCode::Enter => match BpfObject::open(Path::new(&form.path.value)) {
+                    Ok(object) => self.screen = Screen::Object(Box::new(ObjectView::new(object))),
+                    Err(e) => {
+                        form.error = Some(format!("opening {} failed: {}", form.path.value, e))
+                    }
+                },
+                _ => form.path.handle_key(code),
+            },
+            Screen::Object(ref mut view) => match code {
+                // Dropping the object unloads whatever was not pinned.
+                KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('b') => {
+                    let path = view.object.path.clone();
+                    self.screen = Screen::Open(OpenForm {
+                        path: TextInput::new(path),
+                        error: None,
+                    });
+                }
+                KeyCode::Char('j') | KeyCode::Down => view.next_row(),
+                KeyCode::Char('k') | KeyCode::Up => view.previous_row(),
+                KeyCode::Tab => view.switch_focus(),
+                KeyCode::Char(' ') if !view.loaded => view.toggle_load(),
+                KeyCode::Char('p') if !view.loaded => {
+                    if let Some(pin) = view.selected_pin() {
+                        let value = pin.clone().unwrap_or_else(|| "/sys/fs/bpf/".to_string());
+                        self.dialog = Some(Dialog::Pin(TextInput::new(value)));
+                    }
+                }
+                KeyCode::Char('l') if !view.loaded => {
+                    view.loaded = true;
+                    self.dialog = Some(Dialog::Report(view.object.load(), 0));
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn handle_dialog_key(&mut self, code: KeyCode) {
+        let Screen::Object(ref mut view) = self.screen else {
+            self.dialog = None;
+            return;
+        };
+        match self.dialog.take() {
+            Some(Dialog::Pin(mut input)) => match code {
+                KeyCode::Esc => {}
+                KeyCode::Enter => {
+                    if let Some(pin) = view.selected_pin() {
+                        let value = input.value.trim();
+                        *pin = (!value.is_empty()).then(|| value.to_string());
+                    }
+                }
+                _ => {
+                    input.handle_key(code);
+                    self.dialog = Some(Dialog::Pin(input));
+                }
+            },
+            Some(Dialog::Report(report, scroll)) => match code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                    // Without a fresh object there is nothing left to retry with.
+                    if !report.ok
+                        && let Err(e) = view.reopen()
+                    {
+                        let path = view.object.path.clone();
+                        self.screen = Screen::Open(OpenForm {
+                            error: Some(format!("reopening {} failed: {}", path, e)),
+                            path: TextInput::new(path),
+                        });
+                    }
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.dialog = Some(Dialog::Report(report, scroll.saturating_add(1)));
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.dialog = Some(Dialog::Report(report, scroll.saturating_sub(1)));
+                }
+                KeyCode::PageDown => {
+                    self.dialog = Some(Dialog::Report(report, scroll.saturating_add(20)));
+                }
+                KeyCode::PageUp => {
+                    self.dialog = Some(Dialog::Report(report, scroll.saturating_sub(20)));
+                }
+                _ => self.dialog = Some(Dialog::Report(report, scroll)),
+            },
+            None => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rects = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(frame.area());
+
+        match self.screen {
+            Screen::Open(ref form) => {
+                uis::header::render_header(&HEADER_TEXT, frame, rects[0]);
+                let [input_area, error_area] =
+                    Layout::vertical([Constraint::Length(3), Constraint::Min(1)])
+                        .margin(1)
+                        .areas(rects[1]);
+                uis::input::render_input(frame, input_area, "ELF object file", &form.path, true);
+                frame.render_widget(
+                    Paragraph::new(form.error.clone().unwrap_or_default()),
+                    error_area,
+                );
+                uis::footer::render_footer(&["(Enter) open | (Esc) quit"], frame, rects[2]);
+            }
+            Screen::Object(ref mut view) => {
+                let title = format!(
+                    "{} :: {}{}",
+                    HEADER_TEXT[0],
+                    view.object.path,
+                    if view.loaded { " :: loaded" } else { "" }
+                );
+                uis::header::render_header(&[&title], frame, rects[0]);
+
+                let [progs_area, maps_area] =
+                    Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(rects[1]);
+                let border = |pane| {
+                    if view.focus == pane {
+                        BorderType::Thick
+                    } else {
+                        BorderType::Rounded
+                    }
+                };
+                frame.render_widget(
+                    Block::bordered()
+                        .border_type(border(Pane::Programs))
+                        .title(format!("Programs ({})", view.object.programs.len())),
+                    progs_area,
+                );
+                uis::table::render_table(
+                    frame,
+                    progs_area.inner(Margin::new(1, 1)),
+                    &view.object.programs,
+                    &mut view.prog_state,
+                    &["Load", "Name", "Section", "Type", "Pin Path"],
+                );
+                frame.render_widget(
+                    Block::bordered()
+                        .border_type(border(Pane::Maps))
+                        .title(format!("Maps ({})", view.object.maps.len())),
+                    maps_area,
+                );
+                uis::table::render_table(
+                    frame,
+                    maps_area.inner(Margin::new(1, 1)),
+                    &view.object.maps,
+                    &mut view.map_state,
+                    &["Name", "Type", "Key/Value", "Max-Entries", "Pin Path"],
+                );
+
+                uis::footer::render_footer(
+                    &[
+                        "(Esc/q/b) close object | (Tab) programs/maps | (↑/↓) select | (Space) toggle load | (p) pin path | (l) load",
+                    ],
+                    frame,
+                    rects[2],
+                );
+            }
+        }
+
+        match &self.dialog {
+            Some(Dialog::Pin(input)) => {
+                let area = uis::popup::popup_area(frame.area(), 70, 7);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Block::bordered()
+                        .border_type(BorderType::Rounded)
+                        .title("Pin path"),
+                    area,
+                );
+                let [input_area, help_area] =
+                    Layout::vertical([Constraint::Length(3), Constraint::Length(1)])
+                        .margin(1)
+                        .areas(area);
+                uis::input::render_input(frame, input_area, "Path under /sys/fs/bpf", input, true);
+                frame.render_widget(
+                    Paragraph::new("(Enter) set, empty to clear | (Esc) cancel"),
+                    help_area,
+                );
+            }
+            Some(Dialog::Report(report, scroll)) => {
+                let title = if report.ok {
+                    "Loaded :: (Esc) close"
+                } else {
+                    "Load failed :: (↑/↓) scroll | (Esc) close and retry"
+                };
+                uis::popup::render_scroll_popup(frame, title, &report.lines, *scroll);
+            }
+            None => {}
+        }
+    }
+}
@@ -1,4 +1,7 @@
 mod app;
+mod cli;
+mod events;
+mod loader;
 mod maps;
 mod programs;
 use color_eyre::Result;
@@ -10,6 +13,10 @@ mod utils;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
     let terminal = ratatui::init();
     let app_result = app::MainMenu::new().run(terminal);
     ratatui::restore();
@@ -1,26 +1,686 @@
-use std::time::Duration;
+use std::{
+    io,
+    net::IpAddr,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use color_eyre::eyre::Result;
-use libbpf_sys::bpf_map_info;
+use libbpf_sys::{
+    BPF_ANY, BPF_EXIST, BPF_MAP_TYPE_LPM_TRIE, BPF_MAP_TYPE_PERF_EVENT_ARRAY,
+    BPF_MAP_TYPE_PROG_ARRAY, BPF_MAP_TYPE_RINGBUF, BPF_NOEXIST, bpf_map_info, bpf_prog_info,
+};
 use ratatui::{
     DefaultTerminal, Frame,
     crossterm::event::{self, Event, KeyCode, KeyEventKind},
     layout::{Constraint, Layout},
-    widgets::{ScrollbarState, TableState},
+    widgets::{Block, BorderType, Clear, Paragraph, ScrollbarState, TableState},
 };
 
-use crate::{helpers, uis};
+use crate::{
+    events::EventView,
+    helpers::{
+        self,
+        btf::Btf,
+        dump::{self, DumpFormat, ImportPlan},
+        maps::{MapEntry, MapEntryRow, MapUsage},
+    },
+    programs::{Programs, SharedRuntime},
+    uis::{
+        self,
+        export::{ExportPrompt, PromptAction},
+        input::TextInput,
+    },
+    utils::{self, btf::BtfValue},
+};
 
 pub struct Maps {
     state: TableState,
     items: Vec<bpf_map_info>,
+    usage: MapUsage,
+    // When `usage` was taken; walking every program and pin is too slow to do per frame.
+    usage_at: Instant,
     scroll_state: ScrollbarState,
+    screen: Screen,
+    // Screens left by drilling into an inner map, most recent last.
+    history: Vec<Screen>,
+    // Set when opened on a single map from another screen, which leaving it returns to.
+    return_on_back: bool,
+    leaving: bool,
+    dialog: Option<Dialog>,
+    // Handed to the programs screens opened from here.
+    runtime: SharedRuntime,
+}
+
+enum Screen {
+    MapsList,
+    MapInfo(MapInfo),
+    MapContents(Box<MapContents>),
+    Events(Box<EventView>),
+}
+
+impl Screen {
+    /// Ring buffers and perf event arrays have no entries worth looking up, so their
+    /// contents are the live record stream.
+    fn contents(item: bpf_map_info) -> Self {
+        match item.type_ {
+            BPF_MAP_TYPE_RINGBUF | BPF_MAP_TYPE_PERF_EVENT_ARRAY => {
+                Screen::Events(Box::new(EventView::new(item)))
+            }
+            _ => Screen::MapContents(Box::new(MapContents::new(item))),
+        }
+    }
+}
+
+struct MapInfo {
+    map_id: u32,
+    item: bpf_map_info,
+}
+
+struct MapContents {
+    item: bpf_map_info,
+    entries: Vec<MapEntry>,
+    rows: Vec<MapEntryRow>,
+    btf: Option<Btf>,
+    // Loaded programs, to resolve the slots of a PROG_ARRAY.
+    programs: Vec<bpf_prog_info>,
+    hex: bool,
+    cpu_offset: usize,
+    dialog: Option<Dialog>,
+    state: TableState,
+    scroll_state: ScrollbarState,
+}
+
+enum Dialog {
+    Form(EntryForm),
+    Confirm(Vec<String>, Mutation),
+    Export(ExportForm),
+    Import(ImportForm),
+    ImportPreview(ImportPreview),
+    Lookup(LookupForm),
+    Message(String),
+}
+
+struct LookupForm {
+    item: bpf_map_info,
+    address: TextInput,
+    result: Vec<String>,
+}
+
+struct ImportForm {
+    item: bpf_map_info,
+    path: TextInput,
+    error: Option<String>,
+}
+
+impl ImportForm {
+    fn new(item: bpf_map_info) -> Self {
+        Self {
+            item,
+            path: TextInput::default(),
+            error: None,
+        }
+    }
+}
+
+struct ImportPreview {
+    item: bpf_map_info,
+    plan: ImportPlan,
+    lines: Vec<String>,
+    scroll: u16,
+}
+
+struct ExportForm {
+    item: bpf_map_info,
+    prompt: ExportPrompt,
+}
+
+impl ExportForm {
+    fn new(item: bpf_map_info) -> Self {
+        let formats = DumpFormat::ALL
+            .iter()
+            .map(|&format| (format.extension(), dump::default_dump_path(&item, format)))
+            .collect();
+        Self {
+            item,
+            prompt: ExportPrompt::with_formats(format!("Map #{} :: export", item.id), formats),
+        }
+    }
+}
+
+struct EntryForm {
+    item: bpf_map_info,
+    key: TextInput,
+    value: TextInput,
+    flags: u64,
+    editing_value: bool,
+    error: Option<String>,
+}
+
+enum Mutation {
+    Update {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        flags: u64,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+}
+
+const UPDATE_FLAGS: [u64; 3] = [BPF_ANY as u64, BPF_NOEXIST as u64, BPF_EXIST as u64];
+
+impl MapContents {
+    fn new(item: bpf_map_info) -> Self {
+        let mut contents = Self {
+            item,
+            entries: Vec::new(),
+            rows: Vec::new(),
+            btf: Btf::load_by_id(item.btf_id),
+            programs: Vec::new(),
+            hex: false,
+            cpu_offset: 0,
+            dialog: None,
+            state: TableState::default().with_selected(0),
+            scroll_state: ScrollbarState::new(0),
+        };
+        contents.refresh();
+        contents
+    }
+
+    fn refresh(&mut self) {
+        self.entries = helpers::maps::fetch_map_entries(&self.item);
+        if self.is_prog_array() {
+            self.programs = helpers::programs::fetch_programs();
+        }
+        if self.is_lpm() {
+            // Address first, then prefix length, so covering prefixes precede their subnets.
+            self.entries.sort_by_cached_key(|e| {
+                (
+                    e.key.get(4..).unwrap_or_default().to_vec(),
+                    utils::maps::lpm_prefixlen(&e.key),
+                )
+            });
+        }
+        self.build_rows();
+        let i = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .min(self.entries.len().saturating_sub(1));
+        self.state.select(Some(i));
+        self.scroll_state = ScrollbarState::new(self.entries.len()).position(i);
+    }
+
+    fn is_prog_array(&self) -> bool {
+        self.item.type_ == BPF_MAP_TYPE_PROG_ARRAY
+    }
+
+    fn is_lpm(&self) -> bool {
+        self.item.type_ == BPF_MAP_TYPE_LPM_TRIE
+    }
+
+    fn format_key(&self, key: &[u8]) -> Vec<String> {
+        match utils::maps::format_lpm_key(key).filter(|_| self.is_lpm()) {
+            Some(cidr) => vec![cidr],
+            None => {
+                helpers::maps::format_map_bytes(self.btf_view(), self.item.btf_key_type_id, key)
+            }
+        }
+    }
+
+    /// LPM keys may be typed as CIDR; anything else goes through the usual BTF/hex parsing.
+    fn encode_key(&self, text: &str) -> Result<Vec<u8>, String> {
+        let addr = text.trim().split('/').next().unwrap_or_default();
+        if self.is_lpm() && addr.parse::<IpAddr>().is_ok() {
+            return utils::maps::parse_lpm_key(text, self.item.key_size as usize);
+        }
+        helpers::maps::encode_map_input(
+            self.btf_view(),
+            self.item.btf_key_type_id,
+            text,
+            self.item.key_size,
+        )
+    }
+
+    fn build_rows(&mut self) {
+        let btf = self.btf_view();
+        let value_type_id = self.item.btf_value_type_id;
+        let percpu = utils::maps::is_percpu_map(self.item.type_);
+        let cpu_offset = self.cpu_offset;
+
+        self.rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut columns = vec![self.format_key(&entry.key)];
+                if self.is_prog_array() {
+                    return MapEntryRow {
+                        columns: self.tail_call_columns(entry),
+                    };
+                }
+                if utils::maps::is_map_in_map(self.item.type_) {
+                    columns.extend(
+                        entry
+                            .values
+                            .iter()
+                            .map(|value| vec![inner_map_label(value)]),
+                    );
+                    return MapEntryRow { columns };
+                }
+                if !percpu {
+                    columns.extend(
+                        entry.values.iter().map(|value| {
+                            helpers::maps::format_map_bytes(btf, value_type_id, value)
+                        }),
+                    );
+                    return MapEntryRow { columns };
+                }
+
+                columns.extend(
+                    entry
+                        .values
+                        .iter()
+                        .skip(cpu_offset)
+                        .take(CPU_COLUMNS)
+                        .map(|value| helpers::maps::format_map_bytes(btf, value_type_id, value)),
+                );
+                let numbers = entry
+                    .values
+                    .iter()
+                    .map(|value| helpers::maps::numeric_value(btf, value_type_id, value))
+                    .collect::<Option<Vec<_>>>();
+                let aggregates = match numbers {
+                    Some(numbers) if !numbers.is_empty() => [
+                        numbers.iter().sum::<i128>().to_string(),
+                        numbers.iter().min().unwrap().to_string(),
+                        numbers.iter().max().unwrap().to_string(),
+                    ],
+                    _ => ["-".to_string(), "-".to_string(), "-".to_string()],
+                };
+                columns.extend(aggregates.map(|a| vec![a]));
+                MapEntryRow { columns }
+            })
+            .collect();
+    }
+
+    /// Slot index → target program, for PROG_ARRAY maps.
+    fn tail_call_columns(&self, entry: &MapEntry) -> Vec<Vec<String>> {
+        let index = entry.key.first_chunk::<4>().map_or_else(
+            || utils::maps::hex_string(&entry.key),
+            |i| u32::from_ne_bytes(*i).to_string(),
+        );
+        let prog_id = slot_id(entry);
+        let columns = match self.programs.iter().find(|p| p.id == prog_id) {
+            Some(prog) => [
+                index,
+                prog_id.to_string(),
+                utils::programs::cstring_from_i8_array(prog.name).unwrap_or_else(|| "N/A".into()),
+                utils::programs::bpf_prog_type_to_str(prog.type_).to_string(),
+                utils::maps::hex_string(&prog.tag),
+            ],
+            None => [
+                index,
+                prog_id.to_string(),
+                "(not loaded)".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+            ],
+        };
+        columns.map(|c| vec![c]).to_vec()
+    }
+
+    fn headers(&self) -> Vec<String> {
+        if self.is_prog_array() {
+            return ["Slot", "Program ID", "Name", "Type", "Tag"]
+                .map(String::from)
+                .to_vec();
+        }
+        if utils::maps::is_map_in_map(self.item.type_) {
+            return vec!["Key".to_string(), "Inner Map".to_string()];
+        }
+        if !utils::maps::is_percpu_map(self.item.type_) {
+            return vec!["Key".to_string(), "Value".to_string()];
+        }
+        let ncpus = self.entries.first().map_or(0, |e| e.values.len());
+        let mut headers = vec!["Key".to_string()];
+        headers.extend(
+            (self.cpu_offset..ncpus)
+                .take(CPU_COLUMNS)
+                .map(|cpu| format!("CPU{cpu}")),
+        );
+        headers.extend(["Sum", "Min", "Max"].map(String::from));
+        headers
+    }
+
+    fn scroll_cpus(&mut self, forward: bool) {
+        let ncpus = self.entries.first().map_or(0, |e| e.values.len());
+        self.cpu_offset = if forward {
+            (self.cpu_offset + 1).min(ncpus.saturating_sub(CPU_COLUMNS))
+        } else {
+            self.cpu_offset.saturating_sub(1)
+        };
+        self.build_rows();
+    }
+
+    fn btf_view(&self) -> Option<&Btf> {
+        self.btf.as_ref().filter(|_| !self.hex)
+    }
+
+    fn input_text(&self, type_id: u32, bytes: &[u8]) -> String {
+        if let Some(btf) = self.btf_view().filter(|_| type_id != 0) {
+            let text = utils::btf::format_inline(&btf.decode(type_id, bytes));
+            // Elided arrays cannot be parsed back.
+            if !text.contains('…') {
+                return text;
+            }
+        }
+        format!(
+            "hex {}",
+            utils::btf::format_inline(&BtfValue::Bytes(bytes.to_vec()))
+        )
+    }
+
+    fn open_insert(&mut self) {
+        self.dialog = Some(Dialog::Form(EntryForm {
+            item: self.item,
+            key: TextInput::default(),
+            value: TextInput::default(),
+            flags: BPF_ANY as u64,
+            editing_value: false,
+            error: None,
+        }));
+    }
+
+    fn open_edit(&mut self) {
+        let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) else {
+            return;
+        };
+        let key = match utils::maps::format_lpm_key(&entry.key).filter(|_| self.is_lpm()) {
+            Some(cidr) => cidr,
+            None => self.input_text(self.item.btf_key_type_id, &entry.key),
+        };
+        let value = entry.values.first().map_or_else(String::new, |v| {
+            self.input_text(self.item.btf_value_type_id, v)
+        });
+        self.dialog = Some(Dialog::Form(EntryForm {
+            item: self.item,
+            key: TextInput::new(key),
+            value: TextInput::new(value),
+            flags: BPF_EXIST as u64,
+            editing_value: true,
+            error: None,
+        }));
+    }
+
+    fn open_delete(&mut self) {
+        if let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) {
+            let mutation = Mutation::Delete {
+                key: entry.key.clone(),
+            };
+            self.dialog = Some(Dialog::Confirm(self.describe(&mutation), mutation));
+        }
+    }
+
+    fn handle_dialog_key(&mut self, code: KeyCode) {
+        match self.dialog.take() {
+            Some(Dialog::Form(mut form)) => match code {
+                KeyCode::Esc => {}
+                KeyCode::Tab | KeyCode::BackTab => {
+                    form.editing_value = !form.editing_value;
+                    self.dialog = Some(Dialog::Form(form));
+                }
+                KeyCode::Up | KeyCode::Down => {
+                    let i = UPDATE_FLAGS
+                        .iter()
+                        .position(|&f| f == form.flags)
+                        .unwrap_or(0);
+                    let i = if code == KeyCode::Down {
+                        (i + 1) % UPDATE_FLAGS.len()
+                    } else {
+                        (i + UPDATE_FLAGS.len() - 1) % UPDATE_FLAGS.len()
+                    };
+                    form.flags = UPDATE_FLAGS[i];
+                    self.dialog = Some(Dialog::Form(form));
+                }
+                KeyCode::Enter => self.dialog = Some(self.submit_form(form)),
+                _ => {
+                    if form.editing_value {
+                        form.value.handle_key(code);
+                    } else {
+                        form.key.handle_key(code);
+                    }
+                    self.dialog = Some(Dialog::Form(form));
+                }
+            },
+            Some(Dialog::Confirm(lines, mutation)) => match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.dialog = Some(Dialog::Message(self.apply(mutation)));
+                    self.refresh();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {}
+                _ => self.dialog = Some(Dialog::Confirm(lines, mutation)),
+            },
+            Some(Dialog::Lookup(mut form)) => match code {
+                KeyCode::Esc => {}
+                KeyCode::Enter => {
+                    form.result = self.lpm_lookup(&form.address.value);
+                    self.dialog = Some(Dialog::Lookup(form));
+                }
+                _ => {
+                    form.address.handle_key(code);
+                    self.dialog = Some(Dialog::Lookup(form));
+                }
+            },
+            Some(dialog) => {
+                self.dialog = handle_map_dialog_key(dialog, code);
+                if matches!(self.dialog, Some(Dialog::Message(_))) {
+                    self.refresh();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// The program in the selected slot of a PROG_ARRAY.
+    fn selected_program(&self) -> Option<u32> {
+        if !self.is_prog_array() {
+            return None;
+        }
+        let prog_id = slot_id(self.entries.get(self.state.selected()?)?);
+        self.programs
+            .iter()
+            .any(|p| p.id == prog_id)
+            .then_some(prog_id)
+    }
+
+    /// The inner map referenced by the selected slot of a map-in-map.
+    fn selected_inner_map(&self) -> Option<bpf_map_info> {
+        if !utils::maps::is_map_in_map(self.item.type_) {
+            return None;
+        }
+        let id = slot_id(self.entries.get(self.state.selected()?)?);
+        let info = helpers::maps::fetch_maps_by_id(id);
+        (info.id == id).then_some(info)
+    }
+
+    fn open_lookup(&mut self) {
+        if self.is_lpm() {
+            self.dialog = Some(Dialog::Lookup(LookupForm {
+                item: self.item,
+                address: TextInput::default(),
+                result: Vec::new(),
+            }));
+        }
+    }
+
+    /// Longest-prefix match of a typed address: the kernel's answer, plus the prefix that
+    /// produced it, found among the loaded entries.
+    fn lpm_lookup(&self, text: &str) -> Vec<String> {
+        let key = match utils::maps::parse_lpm_key(text, self.item.key_size as usize) {
+            Ok(key) => key,
+            Err(e) => return vec![e],
+        };
+        let prefixlen = utils::maps::lpm_prefixlen(&key);
+        let matched = self
+            .entries
+            .iter()
+            .filter(|e| utils::maps::lpm_prefixlen(&e.key) <= prefixlen)
+            .filter(|e| utils::maps::lpm_key_matches(&e.key, &key[4..]))
+            .max_by_key(|e| utils::maps::lpm_prefixlen(&e.key));
+
+        match helpers::maps::lookup_map_entry(&self.item, &key) {
+            Ok(value) => {
+                let mut lines = vec![format!(
+                    "match: {}",
+                    matched
+                        .and_then(|e| utils::maps::format_lpm_key(&e.key))
+                        .unwrap_or_else(|| "? (refresh to load new entries)".to_string())
+                )];
+                lines.extend(helpers::maps::format_map_bytes(
+                    self.btf_view(),
+                    self.item.btf_value_type_id,
+                    &value,
+                ));
+                lines
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec!["no matching prefix".to_string()],
+            Err(e) => vec![format!("lookup failed: {e}")],
+        }
+    }
+
+    fn submit_form(&self, mut form: EntryForm) -> Dialog {
+        let btf = self.btf_view();
+        let key = self.encode_key(&form.key.value);
+        let value = helpers::maps::encode_map_input(
+            btf,
+            self.item.btf_value_type_id,
+            &form.value.value,
+            self.item.value_size,
+        );
+        match (key, value) {
+            (Ok(key), Ok(value)) => {
+                let mutation = Mutation::Update {
+                    key,
+                    value,
+                    flags: form.flags,
+                };
+                Dialog::Confirm(self.describe(&mutation), mutation)
+            }
+            (Err(e), _) => {
+                form.error = Some(format!("key: {e}"));
+                Dialog::Form(form)
+            }
+            (_, Err(e)) => {
+                form.error = Some(format!("value: {e}"));
+                Dialog::Form(form)
+            }
+        }
+    }
+
+    fn apply(&self, mutation: Mutation) -> String {
+        let (action, result) = match mutation {
+            Mutation::Update { key, value, flags } => (
+                "update",
+                helpers::maps::update_map_entry(&self.item, &key, &[value], flags),
+            ),
+            Mutation::Delete { key } => {
+                ("delete", helpers::maps::delete_map_entry(&self.item, &key))
+            }
+        };
+        match result {
+            Ok(()) => format!("{action} succeeded"),
+            Err(e) => format!("{action} failed: {e}"),
+        }
+    }
+
+    fn describe(&self, mutation: &Mutation) -> Vec<String> {
+        let btf = self.btf_view();
+        let show = |type_id: u32, bytes: &[u8]| match btf {
+            Some(btf) if type_id != 0 => utils::btf::format_inline(&btf.decode(type_id, bytes)),
+            _ => utils::btf::format_inline(&BtfValue::Bytes(bytes.to_vec())),
+        };
+        let show_key = |key: &[u8]| match utils::maps::format_lpm_key(key) {
+            Some(cidr) if self.is_lpm() => cidr,
+            _ => show(self.item.btf_key_type_id, key),
+        };
+        match mutation {
+            Mutation::Update { key, value, flags } => {
+                let mut lines = vec![
+                    format!(
+                        "Update map #{} with {}?",
+                        self.item.id,
+                        utils::maps::bpf_update_flag_to_str(*flags)
+                    ),
+                    format!("key:   {}", show_key(key)),
+                    format!("value: {}", show(self.item.btf_value_type_id, value)),
+                ];
+                // The form edits one value, which the update writes to every CPU's slot.
+                if utils::maps::is_percpu_map(self.item.type_) {
+                    lines.push(format!(
+                        "This value replaces the current value on all {} CPUs, not only CPU0.",
+                        helpers::maps::num_possible_cpus()
+                    ));
+                }
+                lines.push(String::new());
+                lines.push("(y) confirm | (n/Esc) cancel".to_string());
+                lines
+            }
+            Mutation::Delete { key } => vec![
+                format!("Delete entry from map #{}?", self.item.id),
+                format!("key:   {}", show_key(key)),
+                String::new(),
+                "(y) confirm | (n/Esc) cancel".to_string(),
+            ],
+        }
+    }
+
+    fn toggle_hex(&mut self) {
+        self.hex = !self.hex;
+        self.build_rows();
+    }
+
+    fn view_mode(&self) -> &'static str {
+        match (&self.btf, self.hex) {
+            (Some(_), false) => "BTF",
+            (Some(_), true) => "hex (BTF available)",
+            (None, _) => "hex",
+        }
+    }
+
+    fn next_row(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) if i + 1 < self.entries.len() => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i);
+    }
+
+    fn previous_row(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => self.entries.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i);
+    }
 }
 
 const ITEM_HEIGHT: usize = 4;
-const FOOTER_TEXT: [&str; 1] =
-    ["(Esc) quit | (↑) move up | (↓) move down | (←) move left | (→) move right"];
-const HEADER_TEXT: [&str; 1] = ["eBPF Maps"];
+const FOOTER_TEXT: [&str; 1] = [
+    "(Esc) quit | (↑) move up | (↓) move down | (Enter) details | (c) contents | (s) export | (I) import",
+];
+const USAGE_INTERVAL: Duration = Duration::from_secs(3);
+// Number of per-CPU value columns visible at once.
+const CPU_COLUMNS: usize = 4;
+const HEADER_TEXT: [&str; 1] = ["eBPF Maps :: yellow = shared by programs | red = pinned, unused"];
 
 impl Maps {
     pub fn new() -> Self {
@@ -29,6 +689,14 @@ impl Maps {
             scroll_state: ScrollbarState::new((bpf_maps_data.len() - 1) * ITEM_HEIGHT),
             state: TableState::default().with_selected(0),
             items: bpf_maps_data,
+            usage: helpers::maps::fetch_map_usage(),
+            usage_at: Instant::now(),
+            screen: Screen::MapsList,
+            history: Vec::new(),
+            return_on_back: false,
+            leaving: false,
+            dialog: None,
+            runtime: SharedRuntime::default(),
         }
     }
     pub fn next_row(&mut self) {
@@ -60,25 +728,186 @@ impl Maps {
         self.state.select(Some(i));
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        loop {
+    fn handle_dialog_key(&mut self, code: KeyCode) {
+        self.dialog = self
+            .dialog
+            .take()
+            .and_then(|dialog| handle_map_dialog_key(dialog, code));
+    }
+
+    pub fn with_map(info: bpf_map_info, runtime: SharedRuntime) -> Self {
+        let mut maps = Self::new();
+        maps.runtime = runtime;
+        if let Some(i) = maps.items.iter().position(|m| m.id == info.id) {
+            maps.state.select(Some(i));
+            maps.scroll_state = maps.scroll_state.position(i * ITEM_HEIGHT);
+        }
+        maps.screen = Screen::MapInfo(MapInfo {
+            map_id: info.id,
+            item: info,
+        });
+        maps.return_on_back = true;
+        maps
+    }
+
+    /// Opens `screen` on top of the current one, which `back` returns to.
+    fn drill_down(&mut self, screen: Screen) {
+        let outer = std::mem::replace(&mut self.screen, screen);
+        self.history.push(outer);
+    }
+
+    /// Returns to the previous screen, or hands control back to the caller that opened
+    /// this map when there is none.
+    fn back(&mut self) {
+        self.screen = match self.history.pop() {
+            Some(screen) => screen,
+            None if self.return_on_back => {
+                self.leaving = true;
+                return;
+            }
+            None => Screen::MapsList,
+        };
+        if let Screen::MapContents(contents) = &mut self.screen {
+            contents.refresh();
+        }
+    }
+
+    fn breadcrumb(&self) -> String {
+        self.history
+            .iter()
+            .filter_map(|screen| match screen {
+                Screen::MapContents(contents) => Some(contents.item),
+                _ => None,
+            })
+            .map(|map| {
+                format!(
+                    "{} › ",
+                    utils::programs::cstring_from_i8_array(map.name)
+                        .unwrap_or_else(|| format!("#{}", map.id))
+                )
+            })
+            .collect()
+    }
+
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.leaving {
             self.items = helpers::maps::fetch_maps();
+            if matches!(self.screen, Screen::MapsList | Screen::MapInfo(_))
+                && self.usage_at.elapsed() >= USAGE_INTERVAL
+            {
+                self.usage = helpers::maps::fetch_map_usage();
+                self.usage_at = Instant::now();
+            }
+            if let Screen::Events(view) = &mut self.screen {
+                view.poll();
+            }
             terminal.draw(|frame| self.draw(frame))?;
 
             if event::poll(Duration::from_millis(100))? {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press {
                         // let shift_pressed = key.modifiers.contains(KeyModifiers::SHIFT);
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                            KeyCode::Char('j') | KeyCode::Down => self.next_row(),
-                            KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
-                            _ => {}
+                        match self.screen {
+                            _ if self.dialog.is_some() => self.handle_dialog_key(key.code),
+                            Screen::MapsList => match key.code {
+                                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                                KeyCode::Char('j') | KeyCode::Down => self.next_row(),
+                                KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
+                                KeyCode::Enter => {
+                                    if let Some(i) = self.state.selected() {
+                                        let map_id = self.items[i].id;
+                                        let info = helpers::maps::fetch_maps_by_id(map_id);
+                                        self.screen =
+                                            Screen::MapInfo(MapInfo { map_id, item: info });
+                                    }
+                                }
+                                KeyCode::Char('c') => {
+                                    if let Some(i) = self.state.selected() {
+                                        let info =
+                                            helpers::maps::fetch_maps_by_id(self.items[i].id);
+                                        self.screen = Screen::contents(info);
+                                    }
+                                }
+                                KeyCode::Char('s') => {
+                                    if let Some(i) = self.state.selected() {
+                                        let info =
+                                            helpers::maps::fetch_maps_by_id(self.items[i].id);
+                                        self.dialog = Some(Dialog::Export(ExportForm::new(info)));
+                                    }
+                                }
+                                KeyCode::Char('I') => {
+                                    if let Some(i) = self.state.selected() {
+                                        let info =
+                                            helpers::maps::fetch_maps_by_id(self.items[i].id);
+                                        self.dialog = Some(Dialog::Import(ImportForm::new(info)));
+                                    }
+                                }
+                                _ => {}
+                            },
+                            Screen::MapInfo(ref info) => match key.code {
+                                KeyCode::Char('q')
+                                | KeyCode::Esc
+                                | KeyCode::Char('b')
+                                | KeyCode::Backspace => self.back(),
+                                KeyCode::Char('c') => {
+                                    let item = info.item;
+                                    self.drill_down(Screen::contents(item));
+                                }
+                                KeyCode::Char('s') => {
+                                    self.dialog = Some(Dialog::Export(ExportForm::new(info.item)));
+                                }
+                                KeyCode::Char('I') => {
+                                    self.dialog = Some(Dialog::Import(ImportForm::new(info.item)));
+                                }
+                                _ => {}
+                            },
+                            Screen::MapContents(ref mut contents) if contents.dialog.is_some() => {
+                                contents.handle_dialog_key(key.code)
+                            }
+                            Screen::MapContents(ref mut contents) => match key.code {
+                                KeyCode::Char('q')
+                                | KeyCode::Esc
+                                | KeyCode::Char('b')
+                                | KeyCode::Backspace => self.back(),
+                                KeyCode::Enter => {
+                                    if let Some(inner) = contents.selected_inner_map() {
+                                        self.drill_down(Screen::contents(inner));
+                                    } else if let Some(prog_id) = contents.selected_program() {
+                                        Programs::with_program(prog_id, self.runtime.clone())
+                                            .run(terminal)?;
+                                    }
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => contents.next_row(),
+                                KeyCode::Char('k') | KeyCode::Up => contents.previous_row(),
+                                KeyCode::Char('r') => contents.refresh(),
+                                KeyCode::Char('x') => contents.toggle_hex(),
+                                KeyCode::Char('h') | KeyCode::Left => contents.scroll_cpus(false),
+                                KeyCode::Char('l') | KeyCode::Right => contents.scroll_cpus(true),
+                                KeyCode::Char('i') => contents.open_insert(),
+                                KeyCode::Char('e') => contents.open_edit(),
+                                KeyCode::Char('d') => contents.open_delete(),
+                                KeyCode::Char('f') => contents.open_lookup(),
+                                KeyCode::Char('s') => {
+                                    contents.dialog =
+                                        Some(Dialog::Export(ExportForm::new(contents.item)));
+                                }
+                                KeyCode::Char('I') => {
+                                    contents.dialog =
+                                        Some(Dialog::Import(ImportForm::new(contents.item)));
+                                }
+                                _ => {}
+                            },
+                            Screen::Events(ref mut view) => {
+                                if !view.handle_key(key.code) {
+                                    self.back();
+                                }
+                            }
                         }
                     }
                 }
             }
         }
+        Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame) {
@@ -88,17 +917,415 @@ impl Maps {
             Constraint::Length(3),
         ]);
         let rects = vertical.split(frame.area());
+        let breadcrumb = self.breadcrumb();
 
-        uis::header::render_header(&HEADER_TEXT, frame, rects[0]);
-        uis::table::render_table(
-            frame,
-            rects[1],
-            &self.items,
-            &mut self.state,
-            &["ID", "Name", "Max-Entires", "Type"],
-        );
+        match self.screen {
+            Screen::MapsList => {
+                uis::header::render_header(&HEADER_TEXT, frame, rects[0]);
+                let rows = self
+                    .items
+                    .iter()
+                    .map(|item| self.usage.row(item))
+                    .collect::<Vec<_>>();
+                uis::table::render_table(
+                    frame,
+                    rects[1],
+                    &rows,
+                    &mut self.state,
+                    &["ID", "Name", "Max-Entires", "Type", "Used By"],
+                );
+
+                uis::scrollbar::render_scrollbar(&mut self.scroll_state, frame, rects[1]);
+                uis::footer::render_footer(&FOOTER_TEXT, frame, rects[2]);
+            }
+            Screen::MapInfo(ref info) => {
+                let map = info.item;
+                let name = utils::programs::cstring_from_i8_array(map.name)
+                    .unwrap_or_else(|| "N/A".into());
+                let title = format!("eBPF Map #{} :: {}", info.map_id, name);
+                let users = self.usage.users(map.id);
+                let pins = self.usage.pins(map.id);
+
+                let blocks: &[(&str, &[(&str, String)])] = &[
+                    (
+                        "Identity",
+                        &[
+                            ("ID", map.id.to_string()),
+                            (
+                                "Type",
+                                format!(
+                                    "{} ({})",
+                                    map.type_,
+                                    utils::maps::bpf_map_type_to_str(map.type_)
+                                ),
+                            ),
+                            ("Name", name.clone()),
+                            ("Max Entries", map.max_entries.to_string()),
+                        ],
+                    ),
+                    (
+                        "Key & Value",
+                        &[
+                            ("Key Size", format!("{}B", map.key_size)),
+                            ("Value Size", format!("{}B", map.value_size)),
+                            (
+                                "Map Flags",
+                                format!(
+                                    "{:#x} ({})",
+                                    map.map_flags,
+                                    utils::maps::bpf_map_flags_to_str(map.map_flags)
+                                ),
+                            ),
+                        ],
+                    ),
+                    (
+                        "BTF",
+                        &[
+                            ("BTF ID", map.btf_id.to_string()),
+                            ("BTF Key Type ID", map.btf_key_type_id.to_string()),
+                            ("BTF Value Type ID", map.btf_value_type_id.to_string()),
+                            ("BTF vmlinux ID", map.btf_vmlinux_id.to_string()),
+                            (
+                                "BTF vmlinux Value Type ID",
+                                map.btf_vmlinux_value_type_id.to_string(),
+                            ),
+                        ],
+                    ),
+                    (
+                        "Device & Namespaces",
+                        &[
+                            ("IfIndex", map.ifindex.to_string()),
+                            ("Netns Dev", format!("{:#x}", map.netns_dev)),
+                            ("Netns Ino", map.netns_ino.to_string()),
+                            ("Map Extra", format!("{:#x}", map.map_extra)),
+                        ],
+                    ),
+                    (
+                        "Used By",
+                        &[
+                            ("Programs", format_users(&self.usage, users)),
+                            ("Count", users.len().to_string()),
+                            (
+                                "Pinned",
+                                match pins {
+                                    [] => "no".to_string(),
+                                    [path] => path.clone(),
+                                    [path, rest @ ..] => format!("{} (+{} more)", path, rest.len()),
+                                },
+                            ),
+                            (
+                                "Status",
+                                match users.len() {
+                                    0 if !pins.is_empty() => "orphaned (pinned, no users)",
+                                    0 => "unused",
+                                    1 => "private",
+                                    _ => "shared",
+                                }
+                                .to_string(),
+                            ),
+                        ],
+                    ),
+                ];
 
-        uis::scrollbar::render_scrollbar(&mut self.scroll_state, frame, rects[1]);
-        uis::footer::render_footer(&FOOTER_TEXT, frame, rects[2]);
+                uis::header::render_header(&[&title], frame, rects[0]);
+                uis::block::render_kv_columns(frame, rects[1], blocks);
+                uis::footer::render_footer(
+                    &["(Esc/q/b/⌫) back | (c) contents | (s) export | (I) import"],
+                    frame,
+                    rects[2],
+                );
+            }
+            Screen::MapContents(ref mut contents) => {
+                let map = contents.item;
+                let title = format!(
+                    "{}eBPF Map #{} :: {} :: {} entries (key {}B, value {}B) :: {}",
+                    breadcrumb,
+                    map.id,
+                    utils::programs::cstring_from_i8_array(map.name)
+                        .unwrap_or_else(|| "N/A".into()),
+                    contents.entries.len(),
+                    map.key_size,
+                    map.value_size,
+                    contents.view_mode()
+                );
+
+                let headers = contents.headers();
+                let headers = headers.iter().map(String::as_str).collect::<Vec<_>>();
+
+                uis::header::render_header(&[&title], frame, rects[0]);
+                uis::table::render_table(
+                    frame,
+                    rects[1],
+                    &contents.rows,
+                    &mut contents.state,
+                    &headers,
+                );
+                uis::scrollbar::render_scrollbar(&mut contents.scroll_state, frame, rects[1]);
+                let footer = if contents.is_lpm() {
+                    "(Esc/q/b) back | (↑/↓) move | (r) refresh | (x) hex/BTF | (f) LPM lookup | (i) insert | (e) edit | (d) delete | (s) export | (I) import"
+                } else if contents.is_prog_array() {
+                    "(Esc/q/b) back | (↑/↓) move | (Enter) open program | (r) refresh | (d) delete | (s) export | (I) import"
+                } else if utils::maps::is_map_in_map(map.type_) {
+                    "(Esc/q/b) back | (↑/↓) move | (Enter) open inner map | (r) refresh | (d) delete | (s) export | (I) import"
+                } else {
+                    "(Esc/q/b) back | (↑/↓) move | (←/→) CPUs | (r) refresh | (x) hex/BTF | (i) insert | (e) edit | (d) delete | (s) export | (I) import"
+                };
+                uis::footer::render_footer(&[footer], frame, rects[2]);
+                draw_dialog(frame, contents.dialog.as_ref());
+            }
+            Screen::Events(ref mut view) => view.draw(frame, &rects),
+        }
+        draw_dialog(frame, self.dialog.as_ref());
+    }
+}
+
+fn format_users(usage: &MapUsage, users: &[u32]) -> String {
+    if users.is_empty() {
+        return "none".to_string();
+    }
+    users
+        .iter()
+        .map(|&id| match usage.program_name(id) {
+            Some(name) => format!("#{} {}", id, name),
+            None => format!("#{}", id),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The ID held in a map-in-map or PROG_ARRAY slot.
+fn slot_id(entry: &MapEntry) -> u32 {
+    entry
+        .values
+        .first()
+        .and_then(|v| v.first_chunk::<4>())
+        .map_or(0, |id| u32::from_ne_bytes(*id))
+}
+
+/// Resolves a map-in-map value (an inner map ID) to the inner map's name and type.
+fn inner_map_label(value: &[u8]) -> String {
+    let Some(id) = value.first_chunk::<4>().map(|id| u32::from_ne_bytes(*id)) else {
+        return utils::maps::hex_string(value);
+    };
+    let inner = helpers::maps::fetch_maps_by_id(id);
+    if inner.id != id {
+        return format!("#{} (gone)", id);
+    }
+    format!(
+        "#{} {} ({}, {} entries)",
+        id,
+        utils::programs::cstring_from_i8_array(inner.name).unwrap_or_else(|| "N/A".into()),
+        utils::maps::bpf_map_type_to_str(inner.type_),
+        inner.max_entries
+    )
+}
+
+/// Handles the dialogs that only need the map's metadata, shared by every screen.
+fn handle_map_dialog_key(dialog: Dialog, code: KeyCode) -> Option<Dialog> {
+    match dialog {
+        Dialog::Export(form) => handle_export_key(form, code),
+        Dialog::Import(form) => handle_import_key(form, code),
+        Dialog::ImportPreview(preview) => handle_preview_key(preview, code),
+        Dialog::Message(_) => None,
+        dialog => Some(dialog),
+    }
+}
+
+fn handle_import_key(mut form: ImportForm, code: KeyCode) -> Option<Dialog> {
+    match code {
+        KeyCode::Esc => None,
+        KeyCode::Enter => {
+            let plan = dump::read_dump(Path::new(form.path.value.trim()))
+                .and_then(|dump| dump::plan_import(&form.item, &dump));
+            match plan {
+                Ok(plan) => {
+                    let btf = Btf::load_by_id(form.item.btf_id);
+                    Some(Dialog::ImportPreview(ImportPreview {
+                        lines: dump::describe_plan(&form.item, &plan, btf.as_ref()),
+                        item: form.item,
+                        plan,
+                        scroll: 0,
+                    }))
+                }
+                Err(e) => {
+                    form.error = Some(e.to_string());
+                    Some(Dialog::Import(form))
+                }
+            }
+        }
+        _ => {
+            form.path.handle_key(code);
+            Some(Dialog::Import(form))
+        }
+    }
+}
+
+fn handle_preview_key(mut preview: ImportPreview, code: KeyCode) -> Option<Dialog> {
+    match code {
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => None,
+        KeyCode::Char('y') | KeyCode::Char('Y') if !preview.plan.is_empty() => {
+            let (applied, failures) = dump::apply_import(&preview.item, &preview.plan);
+            let mut message = format!("applied {} changes", applied);
+            if let Some(first) = failures.first() {
+                message += &format!(", {} failed (first: {})", failures.len(), first);
+            }
+            Some(Dialog::Message(message))
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            preview.scroll = preview.scroll.saturating_add(1);
+            Some(Dialog::ImportPreview(preview))
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            preview.scroll = preview.scroll.saturating_sub(1);
+            Some(Dialog::ImportPreview(preview))
+        }
+        _ => Some(Dialog::ImportPreview(preview)),
+    }
+}
+
+fn handle_export_key(mut form: ExportForm, code: KeyCode) -> Option<Dialog> {
+    match form.prompt.handle_key(code) {
+        PromptAction::Editing => Some(Dialog::Export(form)),
+        PromptAction::Cancel => None,
+        PromptAction::Write => {
+            let path = form.prompt.path();
+            let format = DumpFormat::ALL[form.prompt.format()];
+            Some(Dialog::Message(
+                match dump::export_map(&form.item, Path::new(path), format) {
+                    Ok(n) => format!("exported {} entries to {}", n, path),
+                    Err(e) => format!("export to {} failed: {}", path, e),
+                },
+            ))
+        }
+    }
+}
+
+fn draw_dialog(frame: &mut Frame, dialog: Option<&Dialog>) {
+    match dialog {
+        Some(Dialog::Form(form)) => {
+            let item = &form.item;
+            let area = uis::popup::popup_area(frame.area(), 70, 11);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title(format!("Map #{} :: insert / update", item.id)),
+                area,
+            );
+            let rows = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .margin(1)
+            .split(area);
+
+            let key_title = format!("Key ({}B)", item.key_size);
+            let value_title = if utils::maps::is_percpu_map(item.type_) {
+                format!("Value ({}B, written to every CPU)", item.value_size)
+            } else {
+                format!("Value ({}B)", item.value_size)
+            };
+            uis::input::render_input(frame, rows[0], &key_title, &form.key, !form.editing_value);
+            uis::input::render_input(
+                frame,
+                rows[1],
+                &value_title,
+                &form.value,
+                form.editing_value,
+            );
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "Flags: {}",
+                    utils::maps::bpf_update_flag_to_str(form.flags)
+                )),
+                rows[2],
+            );
+            frame.render_widget(
+                Paragraph::new(form.error.clone().unwrap_or_default()),
+                rows[3],
+            );
+            frame.render_widget(
+                Paragraph::new("(Tab) switch field | (↑/↓) flags | (Enter) submit | (Esc) cancel"),
+                rows[4],
+            );
+        }
+        Some(Dialog::Confirm(lines, _)) => {
+            uis::popup::render_popup(frame, "Confirm", lines);
+        }
+        Some(Dialog::Export(form)) => {
+            uis::export::render_export_prompt(frame, &form.prompt);
+        }
+        Some(Dialog::Import(form)) => {
+            let area = uis::popup::popup_area(frame.area(), 70, 8);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title(format!("Map #{} :: import", form.item.id)),
+                area,
+            );
+            let rows = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .margin(1)
+            .split(area);
+
+            uis::input::render_input(frame, rows[0], "Dump file (json/csv/bin)", &form.path, true);
+            frame.render_widget(
+                Paragraph::new(form.error.clone().unwrap_or_default()),
+                rows[1],
+            );
+            frame.render_widget(Paragraph::new("(Enter) preview | (Esc) cancel"), rows[2]);
+        }
+        Some(Dialog::ImportPreview(preview)) => {
+            let mut lines = preview.lines.clone();
+            lines.push(String::new());
+            lines.push("(y) apply | (n/Esc) cancel | (↑/↓) scroll".to_string());
+            uis::popup::render_scroll_popup(
+                frame,
+                &format!("Map #{} :: import preview", preview.item.id),
+                &lines,
+                preview.scroll,
+            );
+        }
+        Some(Dialog::Lookup(form)) => {
+            let height = 6 + form.result.len().min(12) as u16;
+            let area = uis::popup::popup_area(frame.area(), 70, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .title(format!("Map #{} :: longest-prefix match", form.item.id)),
+                area,
+            );
+            let rows = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .margin(1)
+            .split(area);
+
+            uis::input::render_input(frame, rows[0], "Address", &form.address, true);
+            frame.render_widget(Paragraph::new(form.result.join("\n")), rows[1]);
+            frame.render_widget(Paragraph::new("(Enter) look up | (Esc) close"), rows[2]);
+        }
+        Some(Dialog::Message(message)) => {
+            uis::popup::render_popup(
+                frame,
+                "Result",
+                &[
+                    message.clone(),
+                    String::new(),
+                    "(any key) close".to_string(),
+                ],
+            );
+        }
+        None => {}
     }
 }
@@ -1,21 +1,45 @@
-use std::time::Duration;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    helpers::{self, programs},
-    uis, utils,
+    helpers::{
+        self,
+        graph::{GraphFormat, TailCallGraph},
+        programs::{self, ProgramLoad, RunHistory, StatsHandle, Subprog},
+    },
+    maps::Maps,
+    uis::{
+        self,
+        export::{ExportPrompt, PromptAction},
+    },
+    utils,
 };
 use color_eyre::Result;
-use libbpf_sys::bpf_prog_info;
+use libbpf_sys::{bpf_map_info, bpf_prog_info};
 use ratatui::{
     DefaultTerminal, Frame,
     crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Direction, Layout},
-    widgets::{ScrollbarState, TableState},
+    layout::{Constraint, Layout, Margin},
+    style::Color,
+    widgets::{Block, BorderType, Paragraph, ScrollbarState, TableState},
 };
 
 const ITEM_HEIGHT: usize = 4;
-const FOOTER_TEXT: [&str; 1] =
-    ["(Esc) quit | (↑) move up | (↓) move down | (←) move left | (→) move right"];
+const FOOTER_TEXT: [&str; 1] = [
+    "(Esc) quit | (↑) move up | (↓) move down | (Enter) details | (t) tail-call graph | (c) CPU top | (e) toggle stats | (g/m) export DOT/Mermaid graph",
+];
+const TAIL_CALL_DOT_PATH: &str = "tail-calls.dot";
+const TOP_INTERVAL: Duration = Duration::from_secs(1);
+const CHART_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    programs::HISTORY_SPAN,
+];
 const HEADER_TEXT: [&str; 1] = ["eBPF Programs"];
 
 pub struct Programs {
@@ -23,27 +47,361 @@ pub struct Programs {
     items: Vec<bpf_prog_info>,
     scroll_state: ScrollbarState,
     screen: Screen,
+    // Set when opened on a single program from another screen, which leaving it returns to.
+    return_on_back: bool,
+    // The Top view ProgramInfo was opened from, which backing out of it returns to.
+    info_from_top: Option<Top>,
+    // Outcome of the last graph export or stats toggle, shown in the header.
+    message: Option<String>,
+    export: Option<GraphExport>,
+    runtime: SharedRuntime,
+    // Whether the kernel currently counts `run_time_ns`/`run_cnt`, by whoever's request.
+    stats_enabled: bool,
+}
+
+/// Runtime stats state that outlives a single Programs screen. Screens opened on a program
+/// from the maps screens share it with the one that opened them, so backing out of one
+/// neither turns stats off nor loses the run history.
+#[derive(Default)]
+pub struct Runtime {
+    // Held while eman keeps runtime stats on; dropping it turns them back off.
+    stats: Option<StatsHandle>,
+    history: RunHistory,
 }
 
+pub type SharedRuntime = Rc<RefCell<Runtime>>;
+
 enum Screen {
     ProgramsList,
-    ProgramInfo(ProgramInfo),
+    ProgramInfo(Box<ProgramInfo>),
+    TailCalls(TailCalls),
+    Disasm(Disasm),
+    Top(Top),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TopSort {
+    Cpu,
+    RunsPerSec,
+    NsPerRun,
+}
+
+impl TopSort {
+    fn next(self) -> Self {
+        match self {
+            TopSort::Cpu => TopSort::RunsPerSec,
+            TopSort::RunsPerSec => TopSort::NsPerRun,
+            TopSort::NsPerRun => TopSort::Cpu,
+        }
+    }
+
+    fn key(self, load: &ProgramLoad) -> f64 {
+        match self {
+            TopSort::Cpu => load.cpu_percent,
+            TopSort::RunsPerSec => load.runs_per_sec,
+            TopSort::NsPerRun => load.ns_per_run,
+        }
+    }
+}
+
+/// Programs ranked by how much CPU they used over the last sampling interval.
+struct Top {
+    previous: HashMap<u32, bpf_prog_info>,
+    sampled_at: Instant,
+    rows: Vec<ProgramLoad>,
+    sort: TopSort,
+    state: TableState,
+    cpus: u32,
+}
+
+impl Top {
+    fn new(items: &[bpf_prog_info]) -> Self {
+        let previous = items.iter().map(|prog| (prog.id, *prog)).collect();
+        let cpus = helpers::programs::online_cpus();
+        Self {
+            // Everything reads zero until the first interval has passed.
+            rows: helpers::programs::program_load(&previous, items, TOP_INTERVAL, cpus),
+            previous,
+            sampled_at: Instant::now(),
+            sort: TopSort::Cpu,
+            state: TableState::default().with_selected(0),
+            cpus,
+        }
+    }
+
+    fn sample(&mut self, items: &[bpf_prog_info]) {
+        let elapsed = self.sampled_at.elapsed();
+        if elapsed < TOP_INTERVAL {
+            return;
+        }
+        self.rows = helpers::programs::program_load(&self.previous, items, elapsed, self.cpus);
+        self.previous = items.iter().map(|prog| (prog.id, *prog)).collect();
+        self.sampled_at = Instant::now();
+        self.sort_rows();
+    }
+
+    /// Re-sorts, keeping the same program selected as it moves.
+    fn sort_rows(&mut self) {
+        let selected = self.selected().map(|row| row.info.id);
+        let sort = self.sort;
+        self.rows.sort_by(|a, b| {
+            sort.key(b)
+                .total_cmp(&sort.key(a))
+                .then(a.info.id.cmp(&b.info.id))
+        });
+        if let Some(i) = selected.and_then(|id| self.rows.iter().position(|r| r.info.id == id)) {
+            self.state.select(Some(i));
+        }
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.sort_rows();
+    }
+
+    fn next_row(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state
+                .select(Some((i + 1).min(self.rows.len().saturating_sub(1))));
+        }
+    }
+
+    fn previous_row(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    fn selected(&self) -> Option<&ProgramLoad> {
+        self.rows.get(self.state.selected()?)
+    }
+}
+
+struct TailCalls {
+    graph: TailCallGraph,
+    lines: Vec<String>,
+    scroll: u16,
+    message: Option<String>,
+    export: Option<GraphExport>,
+}
+
+/// A rendered graph waiting for the user to say where it goes.
+struct GraphExport {
+    prompt: ExportPrompt,
+    contents: String,
+}
+
+/// Feeds a key to the export prompt and returns it while it stays open. Once the file is
+/// written, or writing it failed, `message` says so.
+fn handle_graph_export_key(
+    mut export: GraphExport,
+    code: KeyCode,
+    message: &mut Option<String>,
+) -> Option<GraphExport> {
+    match export.prompt.handle_key(code) {
+        PromptAction::Editing => Some(export),
+        PromptAction::Cancel => None,
+        PromptAction::Write => {
+            let path = export.prompt.path();
+            *message = Some(match fs::write(path, &export.contents) {
+                Ok(()) => format!("wrote {}", path),
+                Err(e) => format!("writing {} failed: {}", path, e),
+            });
+            None
+        }
+    }
+}
+
+impl TailCalls {
+    fn new() -> Self {
+        let graph = helpers::graph::fetch_tail_call_graph();
+        Self {
+            lines: graph.tree(),
+            graph,
+            scroll: 0,
+            message: None,
+            export: None,
+        }
+    }
+
+    fn export_dot(&mut self) {
+        self.export = Some(GraphExport {
+            prompt: ExportPrompt::new(
+                "Export tail-call graph (DOT)".to_string(),
+                TAIL_CALL_DOT_PATH.to_string(),
+            ),
+            contents: self.graph.to_dot(),
+        });
+    }
+}
+
+struct Disasm {
+    prog_id: u32,
+    name: String,
+    kind: &'static str,
+    lines: Vec<String>,
+    // First listing line shown. The view slices `lines` itself rather than using
+    // Paragraph::scroll, whose u16 offset cannot reach past line 65,535.
+    scroll: usize,
+}
+
+impl Disasm {
+    fn xlated(item: &bpf_prog_info) -> Self {
+        let insns = utils::disasm::parse_insns(&helpers::programs::get_xlated_insns(item));
+        let symbols = helpers::disasm::fetch_symbols(item, &insns);
+        let mut lines = utils::disasm::disassemble(&insns, &symbols);
+        if lines.is_empty() {
+            lines.push("no translated instructions available (needs CAP_SYS_ADMIN)".to_string());
+        }
+        Self::new(item, "Xlated instructions", lines)
+    }
+
+    fn jited(item: &bpf_prog_info) -> Self {
+        Self::new(item, "JITed code", helpers::disasm::disassemble_jited(item))
+    }
+
+    fn new(item: &bpf_prog_info, kind: &'static str, lines: Vec<String>) -> Self {
+        Self {
+            prog_id: item.id,
+            name: utils::programs::cstring_from_i8_array(item.name).unwrap_or_else(|| "N/A".into()),
+            kind,
+            lines,
+            scroll: 0,
+        }
+    }
+
+    /// Scrolls so that instruction `insn_off` is at the top, along with the label and
+    /// source lines leading up to it.
+    fn scroll_to_insn(&mut self, insn_off: u32) {
+        let prefix = format!("{:4}: ", insn_off);
+        let Some(mut line) = self.lines.iter().position(|l| l.starts_with(&prefix)) else {
+            return;
+        };
+        while line > 0
+            && (self.lines[line - 1].ends_with(':') || self.lines[line - 1].starts_with(';'))
+        {
+            line -= 1;
+        }
+        self.scroll = line;
+    }
+
+    fn scroll_by(&mut self, delta: i32) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta as isize)
+            .min(self.lines.len().saturating_sub(1));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Maps,
+    Subprogs,
 }
 
 struct ProgramInfo {
     prog_id: u32,
     item: bpf_prog_info,
+    maps: Vec<bpf_map_info>,
+    map_state: TableState,
+    subprogs: Vec<Subprog>,
+    subprog_state: TableState,
+    // Which of the two tables the arrow keys and Enter act on.
+    focus: Pane,
+    // Show run count and latency charts in place of the info blocks.
+    charts: bool,
+    // Index into CHART_WINDOWS.
+    window: usize,
+}
+
+impl ProgramInfo {
+    fn new(prog_id: u32) -> Self {
+        let item = helpers::programs::fetch_programs_by_id(prog_id);
+        let maps = helpers::programs::get_map_ids_by_prog(&item)
+            .into_iter()
+            .map(helpers::maps::fetch_maps_by_id)
+            .filter(|map| map.id != 0)
+            .collect();
+        Self {
+            prog_id,
+            item,
+            maps,
+            map_state: TableState::default().with_selected(0),
+            subprogs: helpers::programs::fetch_subprogs(&item),
+            subprog_state: TableState::default().with_selected(0),
+            focus: Pane::Maps,
+            charts: false,
+            window: 0,
+        }
+    }
+
+    fn focused(&mut self) -> (&mut TableState, usize) {
+        match self.focus {
+            Pane::Maps => (&mut self.map_state, self.maps.len()),
+            Pane::Subprogs => (&mut self.subprog_state, self.subprogs.len()),
+        }
+    }
+
+    fn next_row(&mut self) {
+        let (state, len) = self.focused();
+        if let Some(i) = state.selected() {
+            state.select(Some((i + 1).min(len.saturating_sub(1))));
+        }
+    }
+
+    fn previous_row(&mut self) {
+        let (state, _) = self.focused();
+        if let Some(i) = state.selected() {
+            state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    fn switch_focus(&mut self) {
+        self.focus = match self.focus {
+            Pane::Maps => Pane::Subprogs,
+            Pane::Subprogs => Pane::Maps,
+        };
+    }
+
+    fn selected_subprog(&self) -> Option<&Subprog> {
+        self.subprogs.get(self.subprog_state.selected()?)
+    }
+
+    fn selected_map(&self) -> Option<bpf_map_info> {
+        self.maps.get(self.map_state.selected()?).copied()
+    }
 }
 
 impl Programs {
     pub fn new() -> Self {
+        Self::with_runtime(SharedRuntime::default())
+    }
+
+    fn with_runtime(runtime: SharedRuntime) -> Self {
         let bpf_programs_data = programs::fetch_programs();
         Self {
             state: TableState::default().with_selected(0),
             scroll_state: ScrollbarState::new((bpf_programs_data.len() - 1) * ITEM_HEIGHT),
             items: bpf_programs_data,
             screen: Screen::ProgramsList,
+            return_on_back: false,
+            info_from_top: None,
+            stats_enabled: runtime.borrow().stats.is_some() || programs::stats_enabled(),
+            message: None,
+            export: None,
+            runtime,
+        }
+    }
+
+    pub fn with_program(prog_id: u32, runtime: SharedRuntime) -> Self {
+        let mut programs = Self::with_runtime(runtime);
+        if let Some(i) = programs.items.iter().position(|p| p.id == prog_id) {
+            programs.state.select(Some(i));
+            programs.scroll_state = programs.scroll_state.position(i * ITEM_HEIGHT);
         }
+        programs.screen = Screen::ProgramInfo(Box::new(ProgramInfo::new(prog_id)));
+        programs.return_on_back = true;
+        programs
     }
 
     pub fn next_row(&mut self) {
@@ -76,15 +434,72 @@ impl Programs {
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
 
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+    /// Asks where to write the program/map graph, or only `prog_id`'s neighborhood.
+    fn export_graph(&mut self, format: GraphFormat, prog_id: Option<u32>) {
+        let mut graph = helpers::graph::fetch_usage_graph();
+        let (title, path) = match prog_id {
+            Some(id) => {
+                graph = graph.neighborhood(id);
+                (
+                    format!("Export graph of program #{}", id),
+                    format!("prog-{}-graph.{}", id, format.extension()),
+                )
+            }
+            None => (
+                "Export program/map graph".to_string(),
+                format!("bpf-graph.{}", format.extension()),
+            ),
+        };
+        self.export = Some(GraphExport {
+            prompt: ExportPrompt::new(title, path),
+            contents: graph.render(format),
+        });
+    }
+
+    /// The sysctl misses stats held on by our own BPF_ENABLE_STATS fd.
+    fn stats_on(&self) -> bool {
+        self.runtime.borrow().stats.is_some() || programs::stats_enabled()
+    }
+
+    fn toggle_stats(&mut self) {
+        if self.runtime.borrow_mut().stats.take().is_some() {
+            self.stats_enabled = self.stats_on();
+            self.message = Some(if self.stats_enabled {
+                "released runtime stats, still enabled outside eman".to_string()
+            } else {
+                "runtime stats off".to_string()
+            });
+            return;
+        }
+        self.message = Some(match StatsHandle::enable() {
+            Ok(handle) => {
+                self.runtime.borrow_mut().stats = Some(handle);
+                "runtime stats on".to_string()
+            }
+            Err(e) => format!("enabling runtime stats failed: {}", e),
+        });
+        self.stats_enabled = self.stats_on();
+    }
+
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
         loop {
             self.items = helpers::programs::fetch_programs();
+            self.stats_enabled = self.stats_on();
+            self.runtime.borrow_mut().history.record(&self.items);
+            if let Screen::Top(ref mut top) = self.screen {
+                top.sample(&self.items);
+            }
             terminal.draw(|frame| self.draw(frame))?;
 
             if event::poll(Duration::from_millis(100))? {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press {
                         match self.screen {
+                            _ if self.export.is_some() => {
+                                self.export = self.export.take().and_then(|export| {
+                                    handle_graph_export_key(export, key.code, &mut self.message)
+                                });
+                            }
                             Screen::ProgramsList => match key.code {
                                 KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
                                 KeyCode::Char('j') | KeyCode::Down => self.next_row(),
@@ -92,19 +507,136 @@ impl Programs {
                                 KeyCode::Enter => {
                                     if let Some(i) = self.state.selected() {
                                         let prog_id = self.items[i].id;
-                                        let info = helpers::programs::fetch_programs_by_id(prog_id);
-                                        self.screen = Screen::ProgramInfo(ProgramInfo {
-                                            prog_id,
-                                            item: info,
-                                        });
+                                        self.message = None;
+                                        self.screen = Screen::ProgramInfo(Box::new(
+                                            ProgramInfo::new(prog_id),
+                                        ));
                                     }
                                 }
+                                KeyCode::Char('c') => {
+                                    self.screen = Screen::Top(Top::new(&self.items));
+                                }
+                                KeyCode::Char('e') => self.toggle_stats(),
+                                KeyCode::Char('t') => {
+                                    self.screen = Screen::TailCalls(TailCalls::new());
+                                }
+                                KeyCode::Char('g') => self.export_graph(GraphFormat::Dot, None),
+                                KeyCode::Char('m') => self.export_graph(GraphFormat::Mermaid, None),
                                 _ => {}
                             },
-                            Screen::ProgramInfo(_) => match key.code {
+                            Screen::ProgramInfo(ref mut info) => match key.code {
+                                KeyCode::Char('q')
+                                | KeyCode::Esc
+                                | KeyCode::Char('b')
+                                | KeyCode::Backspace => {
+                                    if self.return_on_back {
+                                        return Ok(());
+                                    }
+                                    self.screen = match self.info_from_top.take() {
+                                        Some(top) => Screen::Top(top),
+                                        None => Screen::ProgramsList,
+                                    };
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => info.next_row(),
+                                KeyCode::Char('k') | KeyCode::Up => info.previous_row(),
+                                KeyCode::Tab => info.switch_focus(),
+                                KeyCode::Char('h') => info.charts = !info.charts,
+                                KeyCode::Char('w') => {
+                                    info.window = (info.window + 1) % CHART_WINDOWS.len();
+                                }
+                                KeyCode::Char('e') => self.toggle_stats(),
+                                KeyCode::Enter if info.focus == Pane::Maps => {
+                                    if let Some(map) = info.selected_map() {
+                                        Maps::with_map(map, self.runtime.clone()).run(terminal)?;
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    if let Some(subprog) = info.selected_subprog() {
+                                        let mut disasm = Disasm::xlated(&info.item);
+                                        disasm.scroll_to_insn(subprog.insn_off);
+                                        self.screen = Screen::Disasm(disasm);
+                                    }
+                                }
+                                KeyCode::Char('g') => {
+                                    let id = info.prog_id;
+                                    self.export_graph(GraphFormat::Dot, Some(id));
+                                }
+                                KeyCode::Char('m') => {
+                                    let id = info.prog_id;
+                                    self.export_graph(GraphFormat::Mermaid, Some(id));
+                                }
+                                KeyCode::Char('x') => {
+                                    self.screen = Screen::Disasm(Disasm::xlated(&info.item));
+                                }
+                                KeyCode::Char('n') => {
+                                    self.screen = Screen::Disasm(Disasm::jited(&info.item));
+                                }
+                                _ => {}
+                            },
+                            Screen::Top(ref mut top) => match key.code {
+                                KeyCode::Char('q')
+                                | KeyCode::Esc
+                                | KeyCode::Char('b')
+                                | KeyCode::Backspace => {
+                                    self.screen = Screen::ProgramsList;
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => top.next_row(),
+                                KeyCode::Char('k') | KeyCode::Up => top.previous_row(),
+                                KeyCode::Char('s') => top.cycle_sort(),
+                                KeyCode::Char('e') => self.toggle_stats(),
+                                KeyCode::Enter => {
+                                    if let Some(row) = top.selected() {
+                                        let info = ProgramInfo::new(row.info.id);
+                                        let screen = Screen::ProgramInfo(Box::new(info));
+                                        if let Screen::Top(top) =
+                                            std::mem::replace(&mut self.screen, screen)
+                                        {
+                                            self.info_from_top = Some(top);
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            },
+                            Screen::Disasm(ref mut disasm) => match key.code {
+                                KeyCode::Char('q')
+                                | KeyCode::Esc
+                                | KeyCode::Char('b')
+                                | KeyCode::Backspace => {
+                                    let id = disasm.prog_id;
+                                    self.screen =
+                                        Screen::ProgramInfo(Box::new(ProgramInfo::new(id)));
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => disasm.scroll_by(1),
+                                KeyCode::Char('k') | KeyCode::Up => disasm.scroll_by(-1),
+                                KeyCode::PageDown => disasm.scroll_by(20),
+                                KeyCode::PageUp => disasm.scroll_by(-20),
+                                KeyCode::Home => disasm.scroll = 0,
+                                KeyCode::End => disasm.scroll_by(i32::MAX / 2),
+                                _ => {}
+                            },
+                            Screen::TailCalls(ref mut tail_calls)
+                                if tail_calls.export.is_some() =>
+                            {
+                                tail_calls.export = tail_calls.export.take().and_then(|export| {
+                                    handle_graph_export_key(
+                                        export,
+                                        key.code,
+                                        &mut tail_calls.message,
+                                    )
+                                });
+                            }
+                            Screen::TailCalls(ref mut tail_calls) => match key.code {
                                 KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('b') => {
                                     self.screen = Screen::ProgramsList;
                                 }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    tail_calls.scroll = tail_calls.scroll.saturating_add(1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    tail_calls.scroll = tail_calls.scroll.saturating_sub(1);
+                                }
+                                KeyCode::Char('r') => *tail_calls = TailCalls::new(),
+                                KeyCode::Char('d') => tail_calls.export_dot(),
                                 _ => {}
                             },
                         }
@@ -124,7 +656,15 @@ impl Programs {
 
         match self.screen {
             Screen::ProgramsList => {
-                uis::footer::render_footer(&HEADER_TEXT, frame, rects[0]);
+                let mut title = format!(
+                    "{} :: runtime stats {}",
+                    HEADER_TEXT[0],
+                    if self.stats_enabled { "on" } else { "off" }
+                );
+                if let Some(message) = &self.message {
+                    title += &format!(" :: {}", message);
+                }
+                uis::footer::render_footer(&[&title], frame, rects[0]);
                 uis::table::render_table(
                     frame,
                     rects[1],
@@ -135,8 +675,21 @@ impl Programs {
                 uis::scrollbar::render_scrollbar(&mut self.scroll_state, frame, rects[1]);
                 uis::footer::render_footer(&FOOTER_TEXT, frame, rects[2]);
             }
-            Screen::ProgramInfo(ref info) => {
+            Screen::ProgramInfo(ref mut info) => {
                 let prog: bpf_prog_info = format_info(info.item);
+                let mut title = format!(
+                    "eBPF Program #{} :: {}{}",
+                    info.prog_id,
+                    utils::programs::cstring_from_i8_array(prog.name)
+                        .unwrap_or_else(|| "N/A".into()),
+                    self.message
+                        .as_ref()
+                        .map_or_else(String::new, |m| format!(" :: {}", m))
+                );
+                if !self.stats_enabled {
+                    title += " :: runtime stats off, Run Time/Run Count are not counting";
+                }
+                uis::header::render_header(&[&title], frame, rects[0]);
 
                 // let columns = Layout::default()
                 //     .direction(Direction::Horizontal)
@@ -239,8 +792,8 @@ impl Programs {
                         &[
                             ("Xlated Len", format!("{}B", prog.xlated_prog_len)),
                             ("JITed Len", format!("{}B", prog.jited_prog_len)),
-                            ("Xlated Prog", format!("{:#x}", prog.xlated_prog_insns)),
-                            ("JITed Prog", format!("{:#x}", prog.jited_prog_insns)),
+                            ("Xlated Insns", (prog.xlated_prog_len / 8).to_string()),
+                            ("JITed Funcs", prog.nr_jited_ksyms.to_string()),
                         ],
                     ),
                     (
@@ -262,10 +815,6 @@ impl Programs {
                         "Maps & Namespaces",
                         &[
                             ("Nr Map IDs", prog.nr_map_ids.to_string()),
-                            (
-                                "Map IDs",
-                                format!("{:?}", helpers::programs::get_map_ids_by_prog(&prog)),
-                            ),
                             ("IfIndex", prog.ifindex.to_string()),
                             ("Netns Dev", format!("{:#x}", prog.netns_dev)),
                             ("Netns Ino", prog.netns_ino.to_string()),
@@ -286,7 +835,6 @@ impl Programs {
                             ("BTF ID", prog.btf_id.to_string()),
                             ("Func Info Rec Size", prog.func_info_rec_size.to_string()),
                             ("Nr Func Info", prog.nr_func_info.to_string()),
-                            ("Func Info", format!("{:#x}", prog.func_info)),
                         ],
                     ),
                     (
@@ -318,51 +866,163 @@ impl Programs {
                     ),
                 ];
 
-                let mid = (blocks.len() + 1) / 2;
-                let (left_blocks, right_blocks) = blocks.split_at(mid);
-
-                let cols = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                    .split(rects[1]);
-
-                let left_chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints(
-                        left_blocks
-                            .iter()
-                            .map(|_| Constraint::Length(7))
-                            .collect::<Vec<_>>(),
-                    )
-                    .split(cols[0]);
-
-                for (i, (title, fields)) in left_blocks.iter().enumerate() {
-                    frame.render_widget(uis::block::render_kv_block(title, fields), left_chunks[i]);
+                let rows = info.maps.len().max(info.subprogs.len()).clamp(1, 8) as u16;
+                let [blocks_area, tables_area] =
+                    Layout::vertical([Constraint::Min(7), Constraint::Length(rows + 3)])
+                        .areas(rects[1]);
+                let [maps_area, subprogs_area] =
+                    Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                        .areas(tables_area);
+                let border = |pane| {
+                    if info.focus == pane {
+                        BorderType::Thick
+                    } else {
+                        BorderType::Rounded
+                    }
+                };
+                if info.charts {
+                    let window = CHART_WINDOWS[info.window];
+                    let series = self.runtime.borrow().history.series(info.prog_id, window);
+                    let [runs_area, latency_area] = Layout::horizontal([
+                        Constraint::Percentage(50),
+                        Constraint::Percentage(50),
+                    ])
+                    .areas(blocks_area);
+                    uis::chart::render_line_chart(
+                        frame,
+                        runs_area,
+                        &format!("Runs/s ({})", uis::chart::format_window(window)),
+                        window,
+                        &[
+                            ("runs/s", &series.runs_per_sec, Color::Cyan),
+                            ("recursion misses/s", &series.misses_per_sec, Color::Red),
+                        ],
+                    );
+                    uis::chart::render_line_chart(
+                        frame,
+                        latency_area,
+                        &format!("Avg runtime ns/run ({})", uis::chart::format_window(window)),
+                        window,
+                        &[("ns/run", &series.ns_per_run, Color::Yellow)],
+                    );
+                } else {
+                    uis::block::render_kv_columns(frame, blocks_area, blocks);
                 }
+                frame.render_widget(
+                    Block::bordered()
+                        .border_type(border(Pane::Maps))
+                        .title(format!("Maps ({})", info.maps.len())),
+                    maps_area,
+                );
+                frame.render_widget(
+                    Block::bordered()
+                        .border_type(border(Pane::Subprogs))
+                        .title(format!("Functions ({})", info.subprogs.len())),
+                    subprogs_area,
+                );
+                uis::table::render_table(
+                    frame,
+                    subprogs_area.inner(Margin::new(1, 1)),
+                    &info.subprogs,
+                    &mut info.subprog_state,
+                    &["Name", "Prototype", "Insn Off", "JITed Size"],
+                );
+                uis::table::render_table(
+                    frame,
+                    maps_area.inner(Margin::new(1, 1)),
+                    &info.maps,
+                    &mut info.map_state,
+                    &["ID", "Name", "Max-Entries", "Type"],
+                );
 
-                let right_chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints(
-                        right_blocks
-                            .iter()
-                            .map(|_| Constraint::Length(7))
-                            .collect::<Vec<_>>(),
-                    )
-                    .split(cols[1]);
-
-                for (i, (title, fields)) in right_blocks.iter().enumerate() {
-                    frame
-                        .render_widget(uis::block::render_kv_block(title, fields), right_chunks[i]);
+                uis::footer::render_footer(
+                    &[
+                        "(Esc/q/b/⌫) back | (Tab) maps/functions | (↑/↓) select | (Enter) open map/function | (x) xlated insns | (n) JITed code | (h/w) charts/window | (e) toggle stats | (g/m) export neighborhood as DOT/Mermaid",
+                    ],
+                    frame,
+                    rects[2],
+                );
+            }
+            Screen::Top(ref mut top) => {
+                let mut title = format!(
+                    "Programs by CPU cost :: {}s interval :: {} CPUs",
+                    TOP_INTERVAL.as_secs(),
+                    top.cpus
+                );
+                if !self.stats_enabled {
+                    title += " :: runtime stats off, press e to enable";
                 }
-
+                if let Some(message) = &self.message {
+                    title += &format!(" :: {}", message);
+                }
+                uis::header::render_header(&[&title], frame, rects[0]);
+                let headers = [
+                    ("Runs/s", TopSort::RunsPerSec),
+                    ("ns/Run", TopSort::NsPerRun),
+                    ("CPU", TopSort::Cpu),
+                ]
+                .map(|(name, sort)| {
+                    if top.sort == sort {
+                        format!("{} ▼", name)
+                    } else {
+                        name.to_string()
+                    }
+                });
+                uis::table::render_table(
+                    frame,
+                    rects[1],
+                    &top.rows,
+                    &mut top.state,
+                    &["ID", "Name", "Type", &headers[0], &headers[1], &headers[2]],
+                );
                 uis::footer::render_footer(
-                    &["(Esc/q/b) back | (↑/↓) n/a | (←/→) n/a"],
+                    &[
+                        "(Esc/q/b/⌫) back | (↑/↓) move | (s) change sort | (e) toggle stats | (Enter) details",
+                    ],
                     frame,
                     rects[2],
                 );
             }
+            Screen::TailCalls(ref tail_calls) => {
+                let title = match &tail_calls.message {
+                    Some(message) => format!("Tail-call graph :: {}", message),
+                    None => "Tail-call graph".to_string(),
+                };
+                uis::header::render_header(&[&title], frame, rects[0]);
+                frame.render_widget(
+                    Paragraph::new(tail_calls.lines.join("\n"))
+                        .scroll((tail_calls.scroll, 0))
+                        .block(Block::bordered().border_type(BorderType::Rounded)),
+                    rects[1],
+                );
+                uis::footer::render_footer(
+                    &["(Esc/q/b) back | (↑/↓) scroll | (r) refresh | (d) export DOT"],
+                    frame,
+                    rects[2],
+                );
+                if let Some(export) = &tail_calls.export {
+                    uis::export::render_export_prompt(frame, &export.prompt);
+                }
+            }
+            Screen::Disasm(ref disasm) => {
+                let title = format!("{} :: #{} {}", disasm.kind, disasm.prog_id, disasm.name);
+                uis::header::render_header(&[&title], frame, rects[0]);
+                let height = rects[1].height.saturating_sub(2) as usize;
+                let visible = disasm.lines.iter().skip(disasm.scroll).take(height);
+                frame.render_widget(
+                    Paragraph::new(visible.map(String::as_str).collect::<Vec<_>>().join("\n"))
+                        .block(Block::bordered().border_type(BorderType::Rounded)),
+                    rects[1],
+                );
+                uis::footer::render_footer(
+                    &["(Esc/q/b/⌫) back | (↑/↓) scroll | (PgUp/PgDn) page | (Home/End) top/bottom"],
+                    frame,
+                    rects[2],
+                );
+            }
+        }
+        if let Some(export) = &self.export {
+            uis::export::render_export_prompt(frame, &export.prompt);
         }
     }
 }
@@ -1,4 +1,6 @@
 use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Style, Stylize},
     text::{Line, Span, Text},
     widgets::{Block, Borders, Paragraph, Wrap},
@@ -18,3 +20,31 @@ pub fn render_kv_block<'a>(title: &'a str, fields: &'a [(&'a str, String)]) -> P
         .block(Block::default().borders(Borders::ALL).title(title))
         .wrap(Wrap { trim: true })
 }
+
+/// Lays out titled key/value blocks in two columns, filling the left column first.
+pub fn render_kv_columns(frame: &mut Frame, area: Rect, blocks: &[(&str, &[(&str, String)])]) {
+    let mid = blocks.len().div_ceil(2);
+    let (left_blocks, right_blocks) = blocks.split_at(mid);
+
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    for (col, col_blocks) in [left_blocks, right_blocks].iter().enumerate() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints(
+                col_blocks
+                    .iter()
+                    .map(|_| Constraint::Length(7))
+                    .collect::<Vec<_>>(),
+            )
+            .split(cols[col]);
+
+        for (i, (title, fields)) in col_blocks.iter().enumerate() {
+            frame.render_widget(render_kv_block(title, fields), chunks[i]);
+        }
+    }
+}
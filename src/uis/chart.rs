@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    symbols::Marker,
+    widgets::{Axis, Block, BorderType, Chart, Dataset, GraphType},
+};
+
+/// A named line of `(x, y)` points.
+pub type Series<'a> = (&'a str, &'a [(f64, f64)], Color);
+
+/// Plots `(seconds ago, value)` series as lines over the last `window`, with the y axis
+/// scaled to the largest value shown.
+pub fn render_line_chart(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    window: Duration,
+    series: &[Series],
+) {
+    let max = series
+        .iter()
+        .flat_map(|(_, points, _)| points.iter().map(|&(_, y)| y))
+        .fold(0.0, f64::max);
+    let top = if max > 0.0 { max * 1.1 } else { 1.0 };
+    let secs = window.as_secs_f64();
+
+    let datasets = series
+        .iter()
+        .map(|&(name, points, color)| {
+            Dataset::default()
+                .name(name)
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(points)
+        })
+        .collect::<Vec<_>>();
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title(title.to_string()),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-secs, 0.0])
+                .labels([format!("-{}", format_window(window)), "now".to_string()]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(["0".to_string(), format!("{:.1}", top)]),
+        );
+    frame.render_widget(chart, area);
+}
+
+pub fn format_window(window: Duration) -> String {
+    match window.as_secs() {
+        s if s >= 3600 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
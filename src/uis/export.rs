@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Constraint, Layout},
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+
+use crate::uis::{self, input::TextInput};
+
+/// Asks where to write an export, and for a second Enter before replacing an existing
+/// file. Optionally offers a choice of formats.
+pub struct ExportPrompt {
+    title: String,
+    path: TextInput,
+    // Format names with the path each suggests; empty when the caller fixed the format.
+    formats: Vec<(&'static str, String)>,
+    format: usize,
+    // Set once the user was told the path exists.
+    overwrite: bool,
+}
+
+pub enum PromptAction {
+    Editing,
+    Cancel,
+    /// The user confirmed `path()`, overwriting it if it exists.
+    Write,
+}
+
+impl ExportPrompt {
+    pub fn new(title: String, path: String) -> Self {
+        Self {
+            title,
+            path: TextInput::new(path),
+            formats: Vec::new(),
+            format: 0,
+            overwrite: false,
+        }
+    }
+
+    /// A prompt whose format is picked with ↑/↓, starting at the first. The path follows the
+    /// format only while the user has not typed one of their own.
+    pub fn with_formats(title: String, formats: Vec<(&'static str, String)>) -> Self {
+        let path = formats
+            .first()
+            .map_or_else(String::new, |(_, path)| path.clone());
+        Self {
+            formats,
+            ..Self::new(title, path)
+        }
+    }
+
+    /// Index of the chosen format in the list given to `with_formats`.
+    pub fn format(&self) -> usize {
+        self.format
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.value.trim()
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> PromptAction {
+        match code {
+            KeyCode::Esc => return PromptAction::Cancel,
+            KeyCode::Up | KeyCode::Down if !self.formats.is_empty() => {
+                let n = self.formats.len();
+                let next = if code == KeyCode::Down {
+                    (self.format + 1) % n
+                } else {
+                    (self.format + n - 1) % n
+                };
+                if self.path.value == self.formats[self.format].1 {
+                    self.path = TextInput::new(self.formats[next].1.clone());
+                    self.overwrite = false;
+                }
+                self.format = next;
+            }
+            KeyCode::Enter => {
+                if self.overwrite || !Path::new(self.path()).exists() {
+                    return PromptAction::Write;
+                }
+                self.overwrite = true;
+            }
+            _ => {
+                self.path.handle_key(code);
+                self.overwrite = false;
+            }
+        }
+        PromptAction::Editing
+    }
+}
+
+pub fn render_export_prompt(frame: &mut Frame, prompt: &ExportPrompt) {
+    let with_formats = !prompt.formats.is_empty();
+    let area = uis::popup::popup_area(frame.area(), 70, if with_formats { 8 } else { 7 });
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(prompt.title.as_str()),
+        area,
+    );
+    let [input_area, format_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(with_formats as u16),
+        Constraint::Length(1),
+    ])
+    .margin(1)
+    .areas(area);
+
+    uis::input::render_input(frame, input_area, "Path", &prompt.path, true);
+    if let Some((name, _)) = prompt.formats.get(prompt.format) {
+        frame.render_widget(Paragraph::new(format!("Format: {}", name)), format_area);
+    }
+    let help = match (prompt.overwrite, with_formats) {
+        (true, _) => "File exists :: (Enter) overwrite | (Esc) cancel",
+        (false, true) => "(↑/↓) format | (Enter) export | (Esc) cancel",
+        (false, false) => "(Enter) export | (Esc) cancel",
+    };
+    frame.render_widget(Paragraph::new(help), help_area);
+}
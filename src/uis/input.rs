@@ -0,0 +1,68 @@
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Position, Rect},
+    style::{Style, Stylize},
+    widgets::{Block, Borders, Paragraph},
+};
+
+#[derive(Default)]
+pub struct TextInput {
+    pub value: String,
+    // Cursor position in chars, not bytes.
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn new(value: String) -> Self {
+        let cursor = value.chars().count();
+        Self { value, cursor }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => {
+                let at = self.byte_index();
+                self.value.insert(at, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let at = self.byte_index();
+                self.value.remove(at);
+            }
+            KeyCode::Delete if self.cursor < self.value.chars().count() => {
+                let at = self.byte_index();
+                self.value.remove(at);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.value.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.value.chars().count(),
+            _ => {}
+        }
+    }
+
+    fn byte_index(&self) -> usize {
+        self.value
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+}
+
+pub fn render_input(frame: &mut Frame, area: Rect, title: &str, input: &TextInput, focused: bool) {
+    let style = if focused {
+        Style::new().bold()
+    } else {
+        Style::new()
+    };
+    let paragraph = Paragraph::new(input.value.as_str())
+        .style(style)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+    if focused {
+        let x = area.x + 1 + input.cursor as u16;
+        frame.set_cursor_position(Position::new(x.min(area.right() - 2), area.y + 1));
+    }
+}
@@ -1,6 +1,10 @@
 pub mod block;
+pub mod chart;
+pub mod export;
 pub mod footer;
 pub mod header;
 pub mod info;
+pub mod input;
+pub mod popup;
 pub mod scrollbar;
 pub mod table;
@@ -0,0 +1,48 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Text},
+    widgets::{Block, BorderType, Clear, Paragraph, Wrap},
+};
+
+pub fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// Draws a centered, bordered box over whatever is already on screen.
+pub fn render_popup(frame: &mut Frame, title: &str, lines: &[String]) {
+    let area = popup_area(frame.area(), 60, lines.len() as u16 + 2);
+    let text = Text::from_iter(lines.iter().map(|l| Line::from(l.as_str())));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text).wrap(Wrap { trim: false }).block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title(title),
+        ),
+        area,
+    );
+}
+
+/// Like `render_popup`, but for content that may not fit: uses most of the screen and
+/// scrolls by `scroll` lines.
+pub fn render_scroll_popup(frame: &mut Frame, title: &str, lines: &[String], scroll: u16) {
+    let height = (frame.area().height * 7 / 10).min(lines.len() as u16 + 2);
+    let area = popup_area(frame.area(), 70, height);
+    let text = Text::from_iter(lines.iter().map(|l| Line::from(l.as_str())));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text).scroll((scroll, 0)).block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title(title),
+        ),
+        area,
+    );
+}
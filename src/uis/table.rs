@@ -11,6 +11,12 @@ use ratatui::{
 pub trait TableRow {
     fn id(&self) -> u32;
     fn cells(&self) -> Vec<Cell<'static>>; // or Vec<Cell<'a>> with lifetime
+    fn height(&self) -> u16 {
+        1
+    }
+    fn style(&self) -> Style {
+        Style::default()
+    }
 }
 
 pub fn render_table<T: TableRow>(
@@ -24,7 +30,11 @@ pub fn render_table<T: TableRow>(
 
     let rows = items
         .iter()
-        .map(|item| Row::new(item.cells()))
+        .map(|item| {
+            Row::new(item.cells())
+                .height(item.height())
+                .style(item.style())
+        })
         .collect::<Vec<_>>();
 
     let widths: Vec<Constraint> = headers.iter().map(|_| Constraint::Min(10)).collect();
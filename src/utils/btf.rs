@@ -0,0 +1,343 @@
+// Arrays longer than this are elided when printed.
+const MAX_ARRAY_ELEMS: usize = 32;
+
+pub enum BtfValue {
+    Signed(i128),
+    Unsigned(u128),
+    Bool(bool),
+    Char(u8),
+    Float(f64),
+    Pointer(u64),
+    Enum {
+        name: Option<String>,
+        value: i128,
+    },
+    Str(String),
+    Array(Vec<BtfValue>),
+    Record {
+        kind: &'static str,
+        name: String,
+        fields: Vec<(String, BtfValue)>,
+    },
+    Bytes(Vec<u8>),
+}
+
+pub fn format_inline(value: &BtfValue) -> String {
+    match value {
+        BtfValue::Signed(v) => v.to_string(),
+        BtfValue::Unsigned(v) => v.to_string(),
+        BtfValue::Bool(v) => v.to_string(),
+        BtfValue::Char(c) => format_char(*c),
+        BtfValue::Float(v) => v.to_string(),
+        BtfValue::Pointer(p) => format!("{:#x}", p),
+        BtfValue::Enum { name, value } => name.clone().unwrap_or_else(|| value.to_string()),
+        BtfValue::Str(s) => format!("{:?}", s),
+        BtfValue::Array(elems) => {
+            let mut parts = elems
+                .iter()
+                .take(MAX_ARRAY_ELEMS)
+                .map(format_inline)
+                .collect::<Vec<_>>();
+            if elems.len() > MAX_ARRAY_ELEMS {
+                parts.push(format!("… ({} more)", elems.len() - MAX_ARRAY_ELEMS));
+            }
+            format!("[{}]", parts.join(", "))
+        }
+        BtfValue::Record { fields, .. } => {
+            let parts = fields
+                .iter()
+                .map(|(name, v)| format!("{}: {}", name, format_inline(v)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", parts.join(", "))
+        }
+        BtfValue::Bytes(bytes) => bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+pub fn format_tree(value: &BtfValue) -> Vec<String> {
+    let mut lines = Vec::new();
+    push_tree(value, "", 0, &mut lines);
+    lines
+}
+
+fn push_tree(value: &BtfValue, prefix: &str, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match value {
+        BtfValue::Record { kind, name, fields } => {
+            let head = if name.is_empty() {
+                format!("{kind} {{")
+            } else {
+                format!("{kind} {name} {{")
+            };
+            lines.push(format!("{indent}{prefix}{head}"));
+            for (field, v) in fields {
+                push_tree(v, &format!("{field}: "), depth + 1, lines);
+            }
+            lines.push(format!("{indent}}}"));
+        }
+        BtfValue::Array(elems) if elems.iter().any(is_compound) => {
+            lines.push(format!("{indent}{prefix}["));
+            for (i, v) in elems.iter().take(MAX_ARRAY_ELEMS).enumerate() {
+                push_tree(v, &format!("[{i}]: "), depth + 1, lines);
+            }
+            if elems.len() > MAX_ARRAY_ELEMS {
+                lines.push(format!(
+                    "{indent}  … ({} more)",
+                    elems.len() - MAX_ARRAY_ELEMS
+                ));
+            }
+            lines.push(format!("{indent}]"));
+        }
+        _ => lines.push(format!("{indent}{prefix}{}", format_inline(value))),
+    }
+}
+
+fn is_compound(value: &BtfValue) -> bool {
+    matches!(value, BtfValue::Record { .. } | BtfValue::Array(_))
+}
+
+fn format_char(c: u8) -> String {
+    if c.is_ascii_graphic() || c == b' ' {
+        format!("'{}'", c as char)
+    } else {
+        format!("'\\x{:02x}'", c)
+    }
+}
+
+/// A value typed by the user, to be encoded against a BTF type.
+pub enum Literal {
+    Int(i128),
+    Float(f64),
+    Char(u8),
+    Str(String),
+    Ident(String),
+    List(Vec<Literal>),
+    Record(Vec<(String, Literal)>),
+}
+
+/// Parses the syntax printed by `format_inline`, e.g. `{saddr: 10, proto: IPPROTO_TCP, name: "x"}`.
+/// Fields may also be written as `name = value`.
+pub fn parse_literal(text: &str) -> Result<Literal, String> {
+    let mut parser = LiteralParser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let literal = parser.literal()?;
+    parser.skip_ws();
+    if parser.pos != parser.chars.len() {
+        return Err(format!("unexpected input at column {}", parser.pos + 1));
+    }
+    Ok(literal)
+}
+
+struct LiteralParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl LiteralParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at column {}", c, self.pos + 1))
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn literal(&mut self) -> Result<Literal, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                loop {
+                    self.skip_ws();
+                    if self.peek() == Some('}') {
+                        self.pos += 1;
+                        return Ok(Literal::Record(fields));
+                    }
+                    let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                    if name.is_empty() {
+                        return Err(format!("expected field name at column {}", self.pos + 1));
+                    }
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(':') | Some('=') => self.pos += 1,
+                        _ => return Err(format!("expected ':' after field {}", name)),
+                    }
+                    fields.push((name, self.literal()?));
+                    self.separator('}')?;
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut elems = Vec::new();
+                loop {
+                    self.skip_ws();
+                    if self.peek() == Some(']') {
+                        self.pos += 1;
+                        return Ok(Literal::List(elems));
+                    }
+                    elems.push(self.literal()?);
+                    self.separator(']')?;
+                }
+            }
+            Some('"') => {
+                self.pos += 1;
+                let mut s = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(Literal::Str(s));
+                        }
+                        Some('\\') => {
+                            self.pos += 1;
+                            s.push(self.escape()?);
+                        }
+                        Some(c) => {
+                            self.pos += 1;
+                            s.push(c);
+                        }
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+            }
+            Some('\'') => {
+                self.pos += 1;
+                let c = match self.peek() {
+                    Some('\\') => {
+                        self.pos += 1;
+                        self.escape()?
+                    }
+                    Some(c) => {
+                        self.pos += 1;
+                        c
+                    }
+                    None => return Err("unterminated character".to_string()),
+                };
+                self.expect('\'')?;
+                u8::try_from(c)
+                    .map(Literal::Char)
+                    .map_err(|_| format!("{:?} is not a single byte", c))
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                let token = self.take_while(|c| c.is_ascii_alphanumeric() || "-+._".contains(c));
+                parse_number(&token)
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => Ok(Literal::Ident(
+                self.take_while(|c| c.is_alphanumeric() || c == '_'),
+            )),
+            _ => Err(format!("expected a value at column {}", self.pos + 1)),
+        }
+    }
+
+    fn separator(&mut self, close: char) -> Result<(), String> {
+        self.skip_ws();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) if c == close => Ok(()),
+            _ => Err(format!(
+                "expected ',' or '{}' at column {}",
+                close,
+                self.pos + 1
+            )),
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = self.peek().ok_or("dangling escape")?;
+        self.pos += 1;
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            '0' => '\0',
+            'x' => {
+                let hex = self
+                    .chars
+                    .get(self.pos..self.pos + 2)
+                    .ok_or("short \\x escape")?;
+                self.pos += 2;
+                let hex = hex.iter().collect::<String>();
+                u8::from_str_radix(&hex, 16).map_err(|_| format!("invalid escape \\x{}", hex))?
+                    as char
+            }
+            c => c,
+        })
+    }
+}
+
+fn parse_number(token: &str) -> Result<Literal, String> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let magnitude = if let Some(hex) = digits.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16)
+    } else if digits.contains('.') || digits.contains('e') {
+        return token
+            .parse::<f64>()
+            .map(Literal::Float)
+            .map_err(|_| format!("invalid number {:?}", token));
+    } else {
+        digits.parse::<i128>()
+    }
+    .map_err(|_| format!("invalid number {:?}", token))?;
+    Ok(Literal::Int(if negative { -magnitude } else { magnitude }))
+}
+
+pub fn to_json(value: &BtfValue) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        BtfValue::Signed(v) => {
+            i64::try_from(*v).map_or_else(|_| Value::from(v.to_string()), Value::from)
+        }
+        BtfValue::Unsigned(v) => {
+            u64::try_from(*v).map_or_else(|_| Value::from(v.to_string()), Value::from)
+        }
+        BtfValue::Bool(v) => Value::from(*v),
+        BtfValue::Char(c) => Value::from(*c),
+        BtfValue::Float(v) => Value::from(*v),
+        BtfValue::Pointer(p) => Value::from(format!("{:#x}", p)),
+        BtfValue::Enum { name, value } => name
+            .clone()
+            .map_or_else(|| to_json(&BtfValue::Signed(*value)), Value::from),
+        BtfValue::Str(s) => Value::from(s.as_str()),
+        BtfValue::Array(elems) => Value::Array(elems.iter().map(to_json).collect()),
+        BtfValue::Record { fields, .. } => Value::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), to_json(v)))
+                .collect(),
+        ),
+        BtfValue::Bytes(bytes) => Value::from(crate::utils::maps::hex_string(bytes)),
+    }
+}
@@ -0,0 +1,866 @@
+// eBPF instruction decoding into bpftool-style text.
+
+use std::collections::{BTreeMap, HashMap};
+
+const BPF_LD: u8 = 0x00;
+const BPF_LDX: u8 = 0x01;
+const BPF_ST: u8 = 0x02;
+const BPF_STX: u8 = 0x03;
+const BPF_ALU: u8 = 0x04;
+const BPF_JMP: u8 = 0x05;
+const BPF_JMP32: u8 = 0x06;
+const BPF_ALU64: u8 = 0x07;
+
+const BPF_IMM: u8 = 0x00;
+const BPF_ABS: u8 = 0x20;
+const BPF_IND: u8 = 0x40;
+const BPF_MEMSX: u8 = 0x80;
+const BPF_ATOMIC: u8 = 0xc0;
+
+const BPF_X: u8 = 0x08;
+const BPF_FETCH: i32 = 0x01;
+const BPF_FUNC_TAIL_CALL: i32 = 12;
+
+pub const BPF_PSEUDO_MAP_FD: u8 = 1;
+pub const BPF_PSEUDO_MAP_VALUE: u8 = 2;
+pub const BPF_PSEUDO_BTF_ID: u8 = 3;
+pub const BPF_PSEUDO_FUNC: u8 = 4;
+pub const BPF_PSEUDO_CALL: u8 = 1;
+pub const BPF_PSEUDO_KFUNC_CALL: u8 = 2;
+
+#[derive(Clone, Copy)]
+pub struct Insn {
+    pub code: u8,
+    pub dst: u8,
+    pub src: u8,
+    pub off: i16,
+    pub imm: i32,
+}
+
+impl Insn {
+    pub fn class(&self) -> u8 {
+        self.code & 0x07
+    }
+
+    /// `ld_imm64` spans two slots; the second only carries the upper 32 bits.
+    pub fn is_ld_imm64(&self) -> bool {
+        self.code == BPF_LD | BPF_IMM | 0x18
+    }
+
+    pub fn is_call(&self) -> bool {
+        self.class() == BPF_JMP && self.code & 0xf0 == 0x80
+    }
+}
+
+/// Names the disassembler substitutes for raw immediates; anything missing prints raw.
+#[derive(Default)]
+pub struct Symbols {
+    /// Kernel functions keyed by their offset from `__bpf_call_base`, which is what helper
+    /// and kfunc call immediates hold once the verifier has fixed them up.
+    pub calls: HashMap<i32, String>,
+    /// kfunc names keyed by vmlinux BTF ID, for calls that still carry one.
+    pub kfuncs: HashMap<i32, String>,
+    pub maps: HashMap<u32, String>,
+    /// PROG_ARRAY map ID → slot → label of the program in it.
+    pub tail_calls: HashMap<u32, BTreeMap<u32, String>>,
+    /// Function names indexed by subprogram, from the JITed symbols.
+    pub subprogs: Vec<String>,
+    /// `; source [file:F line_num:N line_col:C]` comments keyed by the instruction they
+    /// start at.
+    pub source: BTreeMap<usize, Vec<String>>,
+}
+
+impl Symbols {
+    fn call_name(&self, insn: &Insn) -> Option<String> {
+        if let Some(name) = self.calls.get(&insn.imm) {
+            return Some(name.clone());
+        }
+        match insn.src {
+            BPF_PSEUDO_KFUNC_CALL => self.kfuncs.get(&insn.imm).cloned(),
+            // Not fixed up yet, so the immediate is still the helper ID.
+            _ => helper_name(insn.imm),
+        }
+    }
+}
+
+pub fn helper_name(id: i32) -> Option<String> {
+    let name = HELPERS.get(usize::try_from(id).ok()?)?;
+    (id != 0).then(|| format!("bpf_{}", name))
+}
+
+/// Splits a raw instruction buffer, as returned in `xlated_prog_insns`, into instructions.
+pub fn parse_insns(bytes: &[u8]) -> Vec<Insn> {
+    bytes
+        .chunks_exact(8)
+        .map(|raw| Insn {
+            code: raw[0],
+            dst: raw[1] & 0x0f,
+            src: raw[1] >> 4,
+            off: i16::from_ne_bytes([raw[2], raw[3]]),
+            imm: i32::from_ne_bytes([raw[4], raw[5], raw[6], raw[7]]),
+        })
+        .collect()
+}
+
+/// Renders instructions one per line as `   0: (b7) r0 = 0`. The second half of an
+/// `ld_imm64` gets no line of its own, as in bpftool. Subprograms get a label line, source
+/// lines are interleaved before their first instruction, and map references, tail calls
+/// and subprogram calls get a trailing comment.
+pub fn disassemble(insns: &[Insn], symbols: &Symbols) -> Vec<String> {
+    let subprogs = subprog_labels(insns, symbols);
+    let mut lines = Vec::new();
+    // What r2 and r3 were last set to, to tell which slot a tail call jumps through.
+    let mut prog_array = None;
+    let mut slot = None;
+    let mut i = 0;
+    while i < insns.len() {
+        let insn = insns[i];
+        if let Some(label) = subprogs.get(&i) {
+            lines.push(format!("{}:", label));
+        }
+        lines.extend(symbols.source.get(&i).into_iter().flatten().cloned());
+        let mut line = format!(
+            "{:4}: ({:02x}) {}",
+            i,
+            insn.code,
+            format_insn(&insn, insns.get(i + 1), symbols)
+        );
+
+        let comment = if insn.is_ld_imm64() && is_map_ref(&insn) {
+            symbols.maps.get(&(insn.imm as u32)).cloned()
+        } else if insn.is_call() && insn.src == BPF_PSEUDO_CALL {
+            subprogs
+                .get(&call_target(i, &insn))
+                .map(|l| format!("→ {}", l))
+        } else if insn.is_call() && insn.src == 0 && insn.imm == BPF_FUNC_TAIL_CALL {
+            Some(tail_call_target(prog_array, slot, symbols))
+        } else {
+            None
+        };
+        if let Some(comment) = comment {
+            line += &format!("  ; {}", comment);
+        }
+        lines.push(line);
+
+        if insn.is_call() {
+            (prog_array, slot) = (None, None);
+        } else if writes_dst(&insn) && insn.dst == 2 {
+            prog_array =
+                (insn.is_ld_imm64() && insn.src == BPF_PSEUDO_MAP_FD).then_some(insn.imm as u32);
+        } else if writes_dst(&insn) && insn.dst == 3 {
+            // `r3 = K` or `w3 = K`
+            let mov_imm = insn.code & 0xf8 == 0xb0 && insn.class() != BPF_LD;
+            slot = mov_imm.then_some(insn.imm as u32);
+        }
+        i += if insn.is_ld_imm64() { 2 } else { 1 };
+    }
+    lines
+}
+
+fn is_map_ref(insn: &Insn) -> bool {
+    insn.src == BPF_PSEUDO_MAP_FD || insn.src == BPF_PSEUDO_MAP_VALUE
+}
+
+fn writes_dst(insn: &Insn) -> bool {
+    matches!(insn.class(), BPF_ALU | BPF_ALU64 | BPF_LD | BPF_LDX)
+}
+
+/// Index of the instruction a subprogram call lands on. Once the verifier has run, the
+/// relative offset moves from `imm` to `off`.
+fn call_target(i: usize, insn: &Insn) -> usize {
+    let rel = if insn.off != 0 {
+        insn.off as i32
+    } else {
+        insn.imm
+    };
+    (i as i64 + 1 + rel as i64) as usize
+}
+
+fn subprog_labels(insns: &[Insn], symbols: &Symbols) -> BTreeMap<usize, String> {
+    let mut labels = BTreeMap::new();
+    for (i, insn) in insns.iter().enumerate() {
+        if !insn.is_call() || insn.src != BPF_PSEUDO_CALL {
+            continue;
+        }
+        // With a JIT, `imm` is left holding the callee's subprogram index.
+        let name = (insn.off != 0)
+            .then(|| symbols.subprogs.get(insn.imm as usize))
+            .flatten();
+        let target = call_target(i, insn);
+        labels.insert(
+            target,
+            name.cloned()
+                .unwrap_or_else(|| format!("subprog_{}", target)),
+        );
+    }
+    if !labels.is_empty() {
+        let main = symbols.subprogs.first().cloned();
+        labels
+            .entry(0)
+            .or_insert_with(|| main.unwrap_or_else(|| "main".to_string()));
+    }
+    labels
+}
+
+fn tail_call_target(prog_array: Option<u32>, slot: Option<u32>, symbols: &Symbols) -> String {
+    let Some(map_id) = prog_array else {
+        return "tail call".to_string();
+    };
+    let map = symbols
+        .maps
+        .get(&map_id)
+        .cloned()
+        .unwrap_or_else(|| format!("map #{}", map_id));
+    let Some(slot) = slot else {
+        return format!("tail call into {}", map);
+    };
+    let target = symbols
+        .tail_calls
+        .get(&map_id)
+        .and_then(|slots| slots.get(&slot))
+        .map_or("(empty)", String::as_str);
+    format!("tail call into {}[{}] → {}", map, slot, target)
+}
+
+pub fn format_insn(insn: &Insn, next: Option<&Insn>, symbols: &Symbols) -> String {
+    match insn.class() {
+        BPF_ALU | BPF_ALU64 => format_alu(insn),
+        BPF_JMP | BPF_JMP32 => format_jmp(insn, symbols),
+        BPF_LD => format_ld(insn, next),
+        BPF_LDX => {
+            let signed = insn.code & 0xe0 == BPF_MEMSX;
+            format!(
+                "r{} = *({} *)(r{} {:+})",
+                insn.dst,
+                size_name(insn.code, signed),
+                insn.src,
+                insn.off
+            )
+        }
+        BPF_ST => format!(
+            "*({} *)(r{} {:+}) = {}",
+            size_name(insn.code, false),
+            insn.dst,
+            insn.off,
+            insn.imm
+        ),
+        BPF_STX if insn.code & 0xe0 == BPF_ATOMIC => format_atomic(insn),
+        BPF_STX => format!(
+            "*({} *)(r{} {:+}) = r{}",
+            size_name(insn.code, false),
+            insn.dst,
+            insn.off,
+            insn.src
+        ),
+        _ => format!("invalid class {:#x}", insn.code),
+    }
+}
+
+fn size_name(code: u8, signed: bool) -> &'static str {
+    match (code & 0x18, signed) {
+        (0x00, false) => "u32",
+        (0x08, false) => "u16",
+        (0x10, false) => "u8",
+        (0x18, false) => "u64",
+        (0x00, true) => "s32",
+        (0x08, true) => "s16",
+        (0x10, true) => "s8",
+        _ => "s64",
+    }
+}
+
+fn format_alu(insn: &Insn) -> String {
+    let reg = if insn.class() == BPF_ALU64 { 'r' } else { 'w' };
+    let dst = format!("{}{}", reg, insn.dst);
+    let src = if insn.code & BPF_X != 0 {
+        format!("{}{}", reg, insn.src)
+    } else {
+        insn.imm.to_string()
+    };
+    let op = match insn.code & 0xf0 {
+        0x00 => "+=",
+        0x10 => "-=",
+        0x20 => "*=",
+        0x30 if insn.off == 1 => "s/=",
+        0x30 => "/=",
+        0x40 => "|=",
+        0x50 => "&=",
+        0x60 => "<<=",
+        0x70 => ">>=",
+        0x80 => return format!("{dst} = -{dst}"),
+        0x90 if insn.off == 1 => "s%=",
+        0x90 => "%=",
+        0xa0 => "^=",
+        0xb0 if insn.off != 0 && insn.code & BPF_X != 0 => {
+            return format!("{} = (s{}){}", dst, insn.off, src);
+        }
+        0xb0 => "=",
+        0xc0 => "s>>=",
+        0xd0 => {
+            let kind = match (insn.class(), insn.code & BPF_X) {
+                (BPF_ALU64, _) => "bswap",
+                (_, 0) => "le",
+                _ => "be",
+            };
+            return format!("r{0} = {1}{2} r{0}", insn.dst, kind, insn.imm);
+        }
+        _ => return format!("invalid alu op {:#x}", insn.code),
+    };
+    format!("{} {} {}", dst, op, src)
+}
+
+fn format_jmp(insn: &Insn, symbols: &Symbols) -> String {
+    let reg = if insn.class() == BPF_JMP32 { 'w' } else { 'r' };
+    let op = match insn.code & 0xf0 {
+        0x00 if insn.class() == BPF_JMP32 => return format!("gotol pc{:+}", insn.imm),
+        0x00 => return format!("goto pc{:+}", insn.off),
+        0x80 => {
+            return match insn.src {
+                BPF_PSEUDO_CALL if insn.off != 0 => format!("call pc{:+}", insn.off),
+                BPF_PSEUDO_CALL => format!("call pc{:+}", insn.imm),
+                _ => match symbols.call_name(insn) {
+                    Some(name) => format!("call {}#{}", name, insn.imm),
+                    None if insn.src == BPF_PSEUDO_KFUNC_CALL => {
+                        format!("call kernel-function#{}", insn.imm)
+                    }
+                    None => format!("call #{}", insn.imm),
+                },
+            };
+        }
+        0x90 => return "exit".to_string(),
+        0x10 => "==",
+        0x20 => ">",
+        0x30 => ">=",
+        0x40 => "&",
+        0x50 => "!=",
+        0x60 => "s>",
+        0x70 => "s>=",
+        0xa0 => "<",
+        0xb0 => "<=",
+        0xc0 => "s<",
+        0xd0 => "s<=",
+        _ => return format!("invalid jmp op {:#x}", insn.code),
+    };
+    let src = if insn.code & BPF_X != 0 {
+        format!("{}{}", reg, insn.src)
+    } else {
+        format!("{:#x}", insn.imm)
+    };
+    format!(
+        "if {}{} {} {} goto pc{:+}",
+        reg, insn.dst, op, src, insn.off
+    )
+}
+
+fn format_ld(insn: &Insn, next: Option<&Insn>) -> String {
+    match insn.code & 0xe0 {
+        BPF_IMM if insn.is_ld_imm64() => {
+            let hi = next.map_or(0, |n| n.imm as u32);
+            match insn.src {
+                BPF_PSEUDO_MAP_FD => format!("r{} = map[id:{}]", insn.dst, insn.imm),
+                BPF_PSEUDO_MAP_VALUE => {
+                    format!("r{} = map[id:{}][0]+{}", insn.dst, insn.imm, hi)
+                }
+                BPF_PSEUDO_BTF_ID => format!("r{} = btf_id[{}]", insn.dst, insn.imm),
+                BPF_PSEUDO_FUNC => format!("r{} = subprog[pc{:+}]", insn.dst, insn.imm),
+                _ => format!(
+                    "r{} = {:#x}",
+                    insn.dst,
+                    (hi as u64) << 32 | insn.imm as u32 as u64
+                ),
+            }
+        }
+        BPF_ABS => format!("r0 = *({} *)skb[{}]", size_name(insn.code, false), insn.imm),
+        BPF_IND => format!(
+            "r0 = *({} *)skb[r{} + {}]",
+            size_name(insn.code, false),
+            insn.src,
+            insn.imm
+        ),
+        _ => format!("invalid ld mode {:#x}", insn.code),
+    }
+}
+
+fn format_atomic(insn: &Insn) -> String {
+    let size = size_name(insn.code, false);
+    let width = if size == "u64" { "64" } else { "" };
+    let mem = format!("({} *)(r{} {:+})", size, insn.dst, insn.off);
+    let op = match insn.imm & !BPF_FETCH {
+        0x00 => ("+=", "add"),
+        0x40 => ("|=", "or"),
+        0x50 => ("&=", "and"),
+        0xa0 => ("^=", "xor"),
+        0xe0 => {
+            return format!(
+                "r{} = atomic{}_xchg({}, r{})",
+                insn.src, width, mem, insn.src
+            );
+        }
+        0xf0 => {
+            return format!("r0 = atomic{}_cmpxchg({}, r0, r{})", width, mem, insn.src);
+        }
+        _ => return format!("invalid atomic op {:#x}", insn.imm),
+    };
+    if insn.imm & BPF_FETCH != 0 {
+        format!(
+            "r{} = atomic{}_fetch_{}({}, r{})",
+            insn.src, width, op.1, mem, insn.src
+        )
+    } else {
+        format!("lock *{} {} r{}", mem, op.0, insn.src)
+    }
+}
+
+// Helper names indexed by `enum bpf_func_id`, without their `bpf_` prefix.
+const HELPERS: [&str; 212] = [
+    "unspec",
+    "map_lookup_elem",
+    "map_update_elem",
+    "map_delete_elem",
+    "probe_read",
+    "ktime_get_ns",
+    "trace_printk",
+    "get_prandom_u32",
+    "get_smp_processor_id",
+    "skb_store_bytes",
+    "l3_csum_replace",
+    "l4_csum_replace",
+    "tail_call",
+    "clone_redirect",
+    "get_current_pid_tgid",
+    "get_current_uid_gid",
+    "get_current_comm",
+    "get_cgroup_classid",
+    "skb_vlan_push",
+    "skb_vlan_pop",
+    "skb_get_tunnel_key",
+    "skb_set_tunnel_key",
+    "perf_event_read",
+    "redirect",
+    "get_route_realm",
+    "perf_event_output",
+    "skb_load_bytes",
+    "get_stackid",
+    "csum_diff",
+    "skb_get_tunnel_opt",
+    "skb_set_tunnel_opt",
+    "skb_change_proto",
+    "skb_change_type",
+    "skb_under_cgroup",
+    "get_hash_recalc",
+    "get_current_task",
+    "probe_write_user",
+    "current_task_under_cgroup",
+    "skb_change_tail",
+    "skb_pull_data",
+    "csum_update",
+    "set_hash_invalid",
+    "get_numa_node_id",
+    "skb_change_head",
+    "xdp_adjust_head",
+    "probe_read_str",
+    "get_socket_cookie",
+    "get_socket_uid",
+    "set_hash",
+    "setsockopt",
+    "skb_adjust_room",
+    "redirect_map",
+    "sk_redirect_map",
+    "sock_map_update",
+    "xdp_adjust_meta",
+    "perf_event_read_value",
+    "perf_prog_read_value",
+    "getsockopt",
+    "override_return",
+    "sock_ops_cb_flags_set",
+    "msg_redirect_map",
+    "msg_apply_bytes",
+    "msg_cork_bytes",
+    "msg_pull_data",
+    "bind",
+    "xdp_adjust_tail",
+    "skb_get_xfrm_state",
+    "get_stack",
+    "skb_load_bytes_relative",
+    "fib_lookup",
+    "sock_hash_update",
+    "msg_redirect_hash",
+    "sk_redirect_hash",
+    "lwt_push_encap",
+    "lwt_seg6_store_bytes",
+    "lwt_seg6_adjust_srh",
+    "lwt_seg6_action",
+    "rc_repeat",
+    "rc_keydown",
+    "skb_cgroup_id",
+    "get_current_cgroup_id",
+    "get_local_storage",
+    "sk_select_reuseport",
+    "skb_ancestor_cgroup_id",
+    "sk_lookup_tcp",
+    "sk_lookup_udp",
+    "sk_release",
+    "map_push_elem",
+    "map_pop_elem",
+    "map_peek_elem",
+    "msg_push_data",
+    "msg_pop_data",
+    "rc_pointer_rel",
+    "spin_lock",
+    "spin_unlock",
+    "sk_fullsock",
+    "tcp_sock",
+    "skb_ecn_set_ce",
+    "get_listener_sock",
+    "skc_lookup_tcp",
+    "tcp_check_syncookie",
+    "sysctl_get_name",
+    "sysctl_get_current_value",
+    "sysctl_get_new_value",
+    "sysctl_set_new_value",
+    "strtol",
+    "strtoul",
+    "sk_storage_get",
+    "sk_storage_delete",
+    "send_signal",
+    "tcp_gen_syncookie",
+    "skb_output",
+    "probe_read_user",
+    "probe_read_kernel",
+    "probe_read_user_str",
+    "probe_read_kernel_str",
+    "tcp_send_ack",
+    "send_signal_thread",
+    "jiffies64",
+    "read_branch_records",
+    "get_ns_current_pid_tgid",
+    "xdp_output",
+    "get_netns_cookie",
+    "get_current_ancestor_cgroup_id",
+    "sk_assign",
+    "ktime_get_boot_ns",
+    "seq_printf",
+    "seq_write",
+    "sk_cgroup_id",
+    "sk_ancestor_cgroup_id",
+    "ringbuf_output",
+    "ringbuf_reserve",
+    "ringbuf_submit",
+    "ringbuf_discard",
+    "ringbuf_query",
+    "csum_level",
+    "skc_to_tcp6_sock",
+    "skc_to_tcp_sock",
+    "skc_to_tcp_timewait_sock",
+    "skc_to_tcp_request_sock",
+    "skc_to_udp6_sock",
+    "get_task_stack",
+    "load_hdr_opt",
+    "store_hdr_opt",
+    "reserve_hdr_opt",
+    "inode_storage_get",
+    "inode_storage_delete",
+    "d_path",
+    "copy_from_user",
+    "snprintf_btf",
+    "seq_printf_btf",
+    "skb_cgroup_classid",
+    "redirect_neigh",
+    "per_cpu_ptr",
+    "this_cpu_ptr",
+    "redirect_peer",
+    "task_storage_get",
+    "task_storage_delete",
+    "get_current_task_btf",
+    "bprm_opts_set",
+    "ktime_get_coarse_ns",
+    "ima_inode_hash",
+    "sock_from_file",
+    "check_mtu",
+    "for_each_map_elem",
+    "snprintf",
+    "sys_bpf",
+    "btf_find_by_name_kind",
+    "sys_close",
+    "timer_init",
+    "timer_set_callback",
+    "timer_start",
+    "timer_cancel",
+    "get_func_ip",
+    "get_attach_cookie",
+    "task_pt_regs",
+    "get_branch_snapshot",
+    "trace_vprintk",
+    "skc_to_unix_sock",
+    "kallsyms_lookup_name",
+    "find_vma",
+    "loop",
+    "strncmp",
+    "get_func_arg",
+    "get_func_ret",
+    "get_func_arg_cnt",
+    "get_retval",
+    "set_retval",
+    "xdp_get_buff_len",
+    "xdp_load_bytes",
+    "xdp_store_bytes",
+    "copy_from_user_task",
+    "skb_set_tstamp",
+    "ima_file_hash",
+    "kptr_xchg",
+    "map_lookup_percpu_elem",
+    "skc_to_mptcp_sock",
+    "dynptr_from_mem",
+    "ringbuf_reserve_dynptr",
+    "ringbuf_submit_dynptr",
+    "ringbuf_discard_dynptr",
+    "dynptr_read",
+    "dynptr_write",
+    "dynptr_data",
+    "tcp_raw_gen_syncookie_ipv4",
+    "tcp_raw_gen_syncookie_ipv6",
+    "tcp_raw_check_syncookie_ipv4",
+    "tcp_raw_check_syncookie_ipv6",
+    "ktime_get_tai_ns",
+    "user_ringbuf_drain",
+    "cgrp_storage_get",
+    "cgrp_storage_delete",
+];
+
+/// Formats a line_info record the way `bpftool prog dump xlated linum` does.
+pub fn source_comment(source: &str, file: &str, line_col: u32) -> String {
+    format!(
+        "; {} [file:{} line_num:{} line_col:{}]",
+        source.trim(),
+        file,
+        line_col >> 10,
+        line_col & 0x3ff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insn(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Insn {
+        Insn {
+            code,
+            dst,
+            src,
+            off,
+            imm,
+        }
+    }
+
+    fn dump(insns: &[Insn]) -> Vec<String> {
+        disassemble(insns, &Symbols::default())
+    }
+
+    // Expected lines are what `bpftool prog dump xlated` prints for the same instructions.
+
+    #[test]
+    fn alu() {
+        let lines = dump(&[
+            insn(0xb7, 0, 0, 0, 0),
+            insn(0x0f, 1, 2, 0, 0),
+            insn(0x04, 1, 0, 0, 5),
+            insn(0x87, 3, 0, 0, 0),
+            insn(0xc7, 4, 0, 0, 3),
+            insn(0xbc, 5, 6, 0, 0),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "   0: (b7) r0 = 0",
+                "   1: (0f) r1 += r2",
+                "   2: (04) w1 += 5",
+                "   3: (87) r3 = -r3",
+                "   4: (c7) r4 s>>= 3",
+                "   5: (bc) w5 = w6",
+            ]
+        );
+    }
+
+    #[test]
+    fn signed_div_mod_and_movsx() {
+        let lines = dump(&[
+            insn(0x3f, 1, 2, 1, 0),
+            insn(0x97, 1, 0, 1, 7),
+            insn(0x3c, 1, 2, 0, 0),
+            insn(0xbf, 1, 2, 8, 0),
+            insn(0xbc, 1, 2, 16, 0),
+            insn(0x91, 1, 2, 0, 0),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "   0: (3f) r1 s/= r2",
+                "   1: (97) r1 s%= 7",
+                "   2: (3c) w1 /= w2",
+                "   3: (bf) r1 = (s8)r2",
+                "   4: (bc) w1 = (s16)w2",
+                "   5: (91) r1 = *(s8 *)(r2 +0)",
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_swaps() {
+        let lines = dump(&[
+            insn(0xd4, 1, 0, 0, 16),
+            insn(0xdc, 1, 0, 0, 32),
+            insn(0xd7, 1, 0, 0, 64),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "   0: (d4) r1 = le16 r1",
+                "   1: (dc) r1 = be32 r1",
+                "   2: (d7) r1 = bswap64 r1",
+            ]
+        );
+    }
+
+    #[test]
+    fn jumps() {
+        let lines = dump(&[
+            insn(0x15, 1, 0, 2, 0),
+            insn(0x5d, 1, 2, -2, 0),
+            insn(0x16, 3, 0, 1, -1),
+            insn(0xae, 3, 4, 4, 0),
+            insn(0x05, 0, 0, -3, 0),
+            insn(0x06, 0, 0, 0, 100),
+            insn(0x85, 0, 0, 0, 1),
+            insn(0x95, 0, 0, 0, 0),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "   0: (15) if r1 == 0x0 goto pc+2",
+                "   1: (5d) if r1 != r2 goto pc-2",
+                "   2: (16) if w3 == 0xffffffff goto pc+1",
+                "   3: (ae) if w3 < w4 goto pc+4",
+                "   4: (05) goto pc-3",
+                "   5: (06) gotol pc+100",
+                "   6: (85) call bpf_map_lookup_elem#1",
+                "   7: (95) exit",
+            ]
+        );
+    }
+
+    #[test]
+    fn ld_imm64_takes_two_slots() {
+        let mut symbols = Symbols::default();
+        symbols.maps.insert(5, "counters".to_string());
+        let lines = disassemble(
+            &[
+                insn(0x18, 1, 0, 0, 2),
+                insn(0x00, 0, 0, 0, 1),
+                insn(0x18, 2, BPF_PSEUDO_MAP_FD, 0, 5),
+                insn(0x00, 0, 0, 0, 0),
+                insn(0x18, 3, BPF_PSEUDO_MAP_VALUE, 0, 5),
+                insn(0x00, 0, 0, 0, 8),
+                insn(0x95, 0, 0, 0, 0),
+            ],
+            &symbols,
+        );
+        assert_eq!(
+            lines,
+            [
+                "   0: (18) r1 = 0x100000002",
+                "   2: (18) r2 = map[id:5]  ; counters",
+                "   4: (18) r3 = map[id:5][0]+8  ; counters",
+                "   6: (95) exit",
+            ]
+        );
+    }
+
+    #[test]
+    fn atomics() {
+        let lines = dump(&[
+            insn(0xdb, 1, 2, 0, 0x00),
+            insn(0xc3, 1, 2, 8, 0x40),
+            insn(0xdb, 1, 2, -8, 0x01),
+            insn(0xc3, 1, 2, 0, 0xa1),
+            insn(0xdb, 1, 2, 0, 0xe1),
+            insn(0xdb, 1, 2, 0, 0xf1),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "   0: (db) lock *(u64 *)(r1 +0) += r2",
+                "   1: (c3) lock *(u32 *)(r1 +8) |= r2",
+                "   2: (db) r2 = atomic64_fetch_add((u64 *)(r1 -8), r2)",
+                "   3: (c3) r2 = atomic_fetch_xor((u32 *)(r1 +0), r2)",
+                "   4: (db) r2 = atomic64_xchg((u64 *)(r1 +0), r2)",
+                "   5: (db) r0 = atomic64_cmpxchg((u64 *)(r1 +0), r0, r2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn tail_call_names_the_slot() {
+        let mut symbols = Symbols::default();
+        symbols.maps.insert(7, "jmp_table".to_string());
+        symbols
+            .tail_calls
+            .insert(7, BTreeMap::from([(1, "#42 handler".to_string())]));
+        let lines = disassemble(
+            &[
+                insn(0x18, 2, BPF_PSEUDO_MAP_FD, 0, 7),
+                insn(0x00, 0, 0, 0, 0),
+                insn(0xb7, 3, 0, 0, 1),
+                insn(0x85, 0, 0, 0, BPF_FUNC_TAIL_CALL),
+            ],
+            &symbols,
+        );
+        assert_eq!(
+            lines[2],
+            "   3: (85) call bpf_tail_call#12  ; tail call into jmp_table[1] → #42 handler"
+        );
+    }
+
+    #[test]
+    fn parses_raw_instructions() {
+        let raw = [0xb7, 0x10, 0xfe, 0xff, 0x2a, 0x00, 0x00, 0x00];
+        let insns = parse_insns(&raw);
+        assert_eq!(insns.len(), 1);
+        let insn = insns[0];
+        assert_eq!((insn.code, insn.dst, insn.src), (0xb7, 0, 1));
+        assert_eq!((insn.off, insn.imm), (-2, 42));
+    }
+
+    #[test]
+    fn source_lines_precede_their_insn() {
+        let comment = |src, line: u32| source_comment(src, "prog.bpf.c", line << 10 | 5);
+        assert_eq!(
+            comment("\tint x = 0;", 12),
+            "; int x = 0; [file:prog.bpf.c line_num:12 line_col:5]"
+        );
+
+        let mut symbols = Symbols::default();
+        symbols.source.insert(0, vec![comment("int x = 0;", 12)]);
+        symbols
+            .source
+            .insert(1, vec![comment("x += 5;", 13), comment("return x;", 14)]);
+        let lines = disassemble(
+            &[
+                insn(0xb7, 0, 0, 0, 0),
+                insn(0x07, 0, 0, 0, 5),
+                insn(0x95, 0, 0, 0, 0),
+            ],
+            &symbols,
+        );
+        assert_eq!(
+            lines,
+            [
+                "; int x = 0; [file:prog.bpf.c line_num:12 line_col:5]",
+                "   0: (b7) r0 = 0",
+                "; x += 5; [file:prog.bpf.c line_num:13 line_col:5]",
+                "; return x; [file:prog.bpf.c line_num:14 line_col:5]",
+                "   1: (07) r0 += 5",
+                "   2: (95) exit",
+            ]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
 pub fn bpf_map_type_to_str(t: u32) -> &'static str {
     match t {
         1 => "HASH",
@@ -32,3 +34,371 @@ pub fn bpf_map_type_to_str(t: u32) -> &'static str {
         _ => "UNKNOWN",
     }
 }
+
+pub fn bpf_map_flags_to_str(flags: u32) -> String {
+    const FLAGS: [(u32, &str); 19] = [
+        (1 << 0, "NO_PREALLOC"),
+        (1 << 1, "NO_COMMON_LRU"),
+        (1 << 2, "NUMA_NODE"),
+        (1 << 3, "RDONLY"),
+        (1 << 4, "WRONLY"),
+        (1 << 5, "STACK_BUILD_ID"),
+        (1 << 6, "ZERO_SEED"),
+        (1 << 7, "RDONLY_PROG"),
+        (1 << 8, "WRONLY_PROG"),
+        (1 << 9, "CLONE"),
+        (1 << 10, "MMAPABLE"),
+        (1 << 11, "PRESERVE_ELEMS"),
+        (1 << 12, "INNER_MAP"),
+        (1 << 13, "LINK"),
+        (1 << 14, "PATH_FD"),
+        (1 << 15, "VTYPE_BTF_OBJ_FD"),
+        (1 << 16, "TOKEN_FD"),
+        (1 << 17, "SEGV_ON_FAULT"),
+        (1 << 18, "NO_USER_CONV"),
+    ];
+    let names = FLAGS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(" | ")
+    }
+}
+
+pub const HEX_DUMP_WIDTH: usize = 8;
+
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    if bytes.is_empty() {
+        return vec!["<empty>".to_string()];
+    }
+    bytes
+        .chunks(HEX_DUMP_WIDTH)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{:04x}  {:<width$}  |{}|",
+                i * HEX_DUMP_WIDTH,
+                hex,
+                ascii,
+                width = HEX_DUMP_WIDTH * 3 - 1
+            )
+        })
+        .collect()
+}
+
+pub fn is_percpu_map(t: u32) -> bool {
+    matches!(t, 5 | 6 | 10 | 21)
+}
+
+/// Per-CPU values are laid out back to back, each padded to 8 bytes.
+pub fn percpu_value_stride(value_size: u32) -> usize {
+    (value_size as usize).next_multiple_of(8)
+}
+
+/// Parses a kernel CPU list such as "0-3,5,7-8".
+pub fn parse_cpu_list(list: &str) -> Vec<u32> {
+    list.trim()
+        .split(',')
+        .filter(|part| !part.is_empty())
+        .flat_map(|part| match part.split_once('-') {
+            Some((start, end)) => match (start.parse::<u32>(), end.parse::<u32>()) {
+                (Ok(start), Ok(end)) => (start..=end).collect(),
+                _ => Vec::new(),
+            },
+            None => part.parse::<u32>().into_iter().collect(),
+        })
+        .collect()
+}
+
+/// Parses `hex 0a 00 00 01` (whitespace between bytes optional) into raw bytes.
+pub fn parse_hex_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let digits = text
+        .trim()
+        .strip_prefix("hex")
+        .unwrap_or(text)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex digit {:?} in {:?}", c, digits));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(format!("odd number of hex digits in {:?}", digits));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex byte {:?}", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+/// Parses a decimal or `0x` integer into a little-endian buffer of `size` bytes.
+pub fn parse_int_bytes(text: &str, size: usize) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let magnitude = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => digits.parse::<u128>(),
+    }
+    .map_err(|_| format!("expected `hex <bytes>` or an integer, got {:?}", text))?;
+    let value = if negative {
+        (magnitude as i128).wrapping_neg() as u128
+    } else {
+        magnitude
+    };
+
+    if size == 0 || size > 16 {
+        return Err(format!("{size}-byte values need `hex <bytes>` input"));
+    }
+    let fits = size == 16
+        || if negative {
+            magnitude <= 1u128 << (size * 8 - 1)
+        } else {
+            magnitude >> (size * 8) == 0
+        };
+    if !fits {
+        return Err(format!("{} does not fit in {} bytes", text, size));
+    }
+    Ok(value.to_le_bytes()[..size].to_vec())
+}
+
+/// Checks that `value` fits a `bits`-wide integer of the given signedness and returns its
+/// two's-complement bits.
+pub fn int_bits(value: i128, bits: usize, signed: bool) -> Result<u128, String> {
+    let (min, max) = match (signed, bits) {
+        (_, 0) => (0, 0),
+        (true, 128..) => (i128::MIN, i128::MAX),
+        (true, b) => (-(1i128 << (b - 1)), (1i128 << (b - 1)) - 1),
+        (false, 127..) => (0, i128::MAX),
+        (false, b) => (0, (1i128 << b) - 1),
+    };
+    if value < min || value > max {
+        let kind = if signed { "signed" } else { "unsigned" };
+        return Err(format!("{} does not fit in {} {} bits", value, bits, kind));
+    }
+    Ok(value as u128)
+}
+
+pub fn bpf_update_flag_to_str(flags: u64) -> &'static str {
+    match flags {
+        0 => "BPF_ANY",
+        1 => "BPF_NOEXIST",
+        2 => "BPF_EXIST",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Array-like maps have every key preallocated, so entries cannot be deleted.
+pub fn is_array_map(t: u32) -> bool {
+    matches!(t, 2 | 3 | 4 | 6 | 8 | 12)
+}
+
+/// Map-in-map types, whose values are inner map IDs when read from user space.
+pub fn is_map_in_map(t: u32) -> bool {
+    matches!(t, 12 | 13)
+}
+
+/// Renders a `bpf_lpm_trie_key` (host-order prefixlen followed by the address in network
+/// order) as CIDR. Only IPv4 and IPv6 sized keys are recognised.
+pub fn format_lpm_key(key: &[u8]) -> Option<String> {
+    let (prefixlen, data) = key.split_first_chunk::<4>()?;
+    let addr = match data.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(data).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?)),
+        _ => return None,
+    };
+    Some(format!("{}/{}", addr, u32::from_ne_bytes(*prefixlen)))
+}
+
+/// Parses `10.0.0.0/8`, `2001:db8::/32` or a bare address (a full-length prefix) into an
+/// LPM trie key of `key_size` bytes.
+pub fn parse_lpm_key(text: &str, key_size: usize) -> Result<Vec<u8>, String> {
+    let (addr, prefix) = match text.trim().split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (text.trim(), None),
+    };
+    let data = match addr.parse::<IpAddr>() {
+        Ok(IpAddr::V4(addr)) => addr.octets().to_vec(),
+        Ok(IpAddr::V6(addr)) => addr.octets().to_vec(),
+        Err(_) => return Err(format!("invalid address {:?}", addr)),
+    };
+    if data.len() + 4 != key_size {
+        return Err(format!(
+            "{} needs a {}-byte key, map keys are {} bytes",
+            addr,
+            data.len() + 4,
+            key_size
+        ));
+    }
+    let max = data.len() as u32 * 8;
+    let prefixlen = match prefix {
+        Some(prefix) => prefix
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|&p| p <= max)
+            .ok_or_else(|| format!("invalid prefix length {:?}", prefix))?,
+        None => max,
+    };
+    let mut key = prefixlen.to_ne_bytes().to_vec();
+    key.extend(data);
+    Ok(key)
+}
+
+pub fn lpm_prefixlen(key: &[u8]) -> u32 {
+    key.first_chunk::<4>().map_or(0, |p| u32::from_ne_bytes(*p))
+}
+
+/// Whether the prefix held in LPM key `key` covers the address bytes `addr`.
+pub fn lpm_key_matches(key: &[u8], addr: &[u8]) -> bool {
+    let bits = lpm_prefixlen(key) as usize;
+    let data = key.get(4..).unwrap_or_default();
+    if data.len() != addr.len() || bits > data.len() * 8 {
+        return false;
+    }
+    let (full, rest) = (bits / 8, bits % 8);
+    data[..full] == addr[..full] && (rest == 0 || (data[full] ^ addr[full]) >> (8 - rest) == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0"), [0]);
+        assert_eq!(parse_cpu_list("0-3\n"), [0, 1, 2, 3]);
+        assert_eq!(parse_cpu_list("0-1,4,6-7"), [0, 1, 4, 6, 7]);
+        assert_eq!(parse_cpu_list(""), Vec::<u32>::new());
+        assert_eq!(parse_cpu_list("2,x,3"), [2, 3]);
+    }
+
+    #[test]
+    fn int_bytes() {
+        assert_eq!(parse_int_bytes("1", 4), Ok(vec![1, 0, 0, 0]));
+        assert_eq!(parse_int_bytes(" 0x1234 ", 2), Ok(vec![0x34, 0x12]));
+        assert_eq!(parse_int_bytes("-1", 2), Ok(vec![0xff, 0xff]));
+        assert_eq!(parse_int_bytes("-128", 1), Ok(vec![0x80]));
+        assert_eq!(parse_int_bytes("255", 1), Ok(vec![0xff]));
+        assert!(parse_int_bytes("256", 1).is_err());
+        assert!(parse_int_bytes("-129", 1).is_err());
+        assert!(parse_int_bytes("12", 17).is_err());
+        assert!(parse_int_bytes("abc", 4).is_err());
+        assert_eq!(parse_int_bytes("-1", 16), Ok(vec![0xff; 16]));
+    }
+
+    #[test]
+    fn int_ranges() {
+        assert_eq!(int_bits(255, 8, false), Ok(0xff));
+        assert!(int_bits(300, 8, false).is_err());
+        assert!(int_bits(-1, 8, false).is_err());
+        assert_eq!(int_bits(-128, 8, true), Ok((-128i128) as u128));
+        assert!(int_bits(128, 8, true).is_err());
+        assert!(int_bits(-129, 8, true).is_err());
+        // Bitfields: an unsigned 3-bit field holds 0..=7, a signed one -4..=3.
+        assert_eq!(int_bits(7, 3, false), Ok(7));
+        assert!(int_bits(-1, 3, false).is_err());
+        assert_eq!(int_bits(-4, 3, true), Ok((-4i128) as u128));
+        assert!(int_bits(4, 3, true).is_err());
+        assert_eq!(int_bits(i128::MIN, 128, true), Ok(1 << 127));
+        assert_eq!(int_bits(u64::MAX as i128, 64, false), Ok(u64::MAX as u128));
+        assert!(int_bits(1, 0, false).is_err());
+    }
+
+    #[test]
+    fn hex_bytes() {
+        assert_eq!(parse_hex_bytes("hex 0a 00 ff"), Ok(vec![0x0a, 0x00, 0xff]));
+        assert_eq!(parse_hex_bytes("0a00FF"), Ok(vec![0x0a, 0x00, 0xff]));
+        assert!(parse_hex_bytes("hex 0a 0").is_err());
+        assert!(parse_hex_bytes("hex zz").is_err());
+        // Even byte length, but slicing by bytes would split the 'é'.
+        assert!(parse_hex_bytes("hex aé1").is_err());
+    }
+
+    #[test]
+    fn lpm_keys() {
+        let v4 = parse_lpm_key("10.0.0.0/8", 8).unwrap();
+        assert_eq!(v4[..4], 8u32.to_ne_bytes());
+        assert_eq!(v4[4..], [10, 0, 0, 0]);
+        assert_eq!(format_lpm_key(&v4).as_deref(), Some("10.0.0.0/8"));
+
+        let v6 = parse_lpm_key(" 2001:db8::/32 ", 20).unwrap();
+        assert_eq!(lpm_prefixlen(&v6), 32);
+        assert_eq!(format_lpm_key(&v6).as_deref(), Some("2001:db8::/32"));
+
+        // A bare address is a full-length prefix.
+        let host = parse_lpm_key("192.168.1.1", 8).unwrap();
+        assert_eq!(format_lpm_key(&host).as_deref(), Some("192.168.1.1/32"));
+        let host6 = parse_lpm_key("::1/128", 20).unwrap();
+        assert_eq!(lpm_prefixlen(&host6), 128);
+        assert_eq!(format_lpm_key(&host6).as_deref(), Some("::1/128"));
+
+        assert!(parse_lpm_key("10.0.0.0/33", 8).is_err());
+        assert!(parse_lpm_key("::/129", 20).is_err());
+        assert!(parse_lpm_key("10.0.0.0/8", 20).is_err());
+        assert!(parse_lpm_key("::/0", 8).is_err());
+        assert!(parse_lpm_key("nope/8", 8).is_err());
+        assert_eq!(format_lpm_key(&[0; 7]), None);
+    }
+
+    #[test]
+    fn lpm_matches() {
+        let any = parse_lpm_key("0.0.0.0/0", 8).unwrap();
+        assert!(lpm_key_matches(&any, &[255, 1, 2, 3]));
+
+        let host = parse_lpm_key("192.168.1.1/32", 8).unwrap();
+        assert!(lpm_key_matches(&host, &[192, 168, 1, 1]));
+        assert!(!lpm_key_matches(&host, &[192, 168, 1, 2]));
+
+        // /20 ends four bits into the third byte: 10.1.16.0 - 10.1.31.255.
+        let net = parse_lpm_key("10.1.16.0/20", 8).unwrap();
+        assert!(lpm_key_matches(&net, &[10, 1, 16, 0]));
+        assert!(lpm_key_matches(&net, &[10, 1, 31, 255]));
+        assert!(!lpm_key_matches(&net, &[10, 1, 32, 0]));
+        assert!(!lpm_key_matches(&net, &[10, 1, 15, 255]));
+
+        let host6 = parse_lpm_key("2001:db8::1/128", 20).unwrap();
+        let mut addr: [u8; 16] = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        assert!(lpm_key_matches(&host6, &addr));
+        addr[15] = 2;
+        assert!(!lpm_key_matches(&host6, &addr));
+
+        // Mismatched families never match.
+        assert!(!lpm_key_matches(&any, &addr));
+        // Neither does a key claiming more bits than it holds.
+        let mut bad = any.clone();
+        bad[..4].copy_from_slice(&33u32.to_ne_bytes());
+        assert!(!lpm_key_matches(&bad, &[0; 4]));
+    }
+}
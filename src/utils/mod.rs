@@ -1,2 +1,4 @@
+pub mod btf;
+pub mod disasm;
 pub mod maps;
 pub mod programs;